@@ -0,0 +1,2 @@
+-- Amounts used to be written from f64, which could leave long binary expansions behind
+ALTER TABLE transactions ALTER COLUMN amount TYPE NUMERIC(19, 4) USING ROUND(amount, 4);
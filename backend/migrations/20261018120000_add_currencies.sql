@@ -0,0 +1,7 @@
+ALTER TABLE users
+    ADD COLUMN base_currency TEXT NOT NULL DEFAULT 'CAD'
+    CONSTRAINT users_base_currency_iso4217 CHECK (base_currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE transactions
+    ADD COLUMN currency TEXT NOT NULL DEFAULT 'CAD'
+    CONSTRAINT transactions_currency_iso4217 CHECK (currency ~ '^[A-Z]{3}$');
@@ -0,0 +1,45 @@
+CREATE TABLE exchange_rates (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rate_date DATE NOT NULL,
+    from_currency TEXT NOT NULL CHECK (from_currency ~ '^[A-Z]{3}$'),
+    to_currency TEXT NOT NULL CHECK (to_currency ~ '^[A-Z]{3}$'),
+    rate NUMERIC(19, 10) NOT NULL CHECK (rate > 0),
+    PRIMARY KEY (user_id, rate_date, from_currency, to_currency),
+    CONSTRAINT no_self_conversion CHECK (from_currency <> to_currency)
+);
+
+-- Finds the rate to convert p_from into p_to, using the most recent rate on or before p_at.
+-- Direct rates are preferred, then inverted ones, and finally a cross rate through EUR
+-- (the base of the ECB reference rates). Returns NULL when no rate is known.
+CREATE OR REPLACE FUNCTION exchange_rate_on(p_user_id UUID, p_from TEXT, p_to TEXT, p_at TIMESTAMPTZ)
+RETURNS NUMERIC AS $$
+DECLARE
+    v_on DATE := (p_at AT TIME ZONE 'UTC')::date;
+    v_rate NUMERIC;
+BEGIN
+    IF p_from = p_to THEN
+        RETURN 1;
+    END IF;
+
+    SELECT candidates.rate INTO v_rate
+    FROM (
+        SELECT er.rate_date, er.rate, 0 AS priority
+        FROM exchange_rates er
+        WHERE er.user_id = p_user_id AND er.from_currency = p_from AND er.to_currency = p_to
+        AND er.rate_date <= v_on
+        UNION ALL
+        SELECT er.rate_date, 1 / er.rate, 1 AS priority
+        FROM exchange_rates er
+        WHERE er.user_id = p_user_id AND er.from_currency = p_to AND er.to_currency = p_from
+        AND er.rate_date <= v_on
+    ) candidates
+    ORDER BY candidates.rate_date DESC, candidates.priority
+    LIMIT 1;
+
+    IF v_rate IS NULL AND p_from <> 'EUR' AND p_to <> 'EUR' THEN
+        v_rate := exchange_rate_on(p_user_id, p_from, 'EUR', p_at) * exchange_rate_on(p_user_id, 'EUR', p_to, p_at);
+    END IF;
+
+    RETURN v_rate;
+END;
+$$ LANGUAGE plpgsql STABLE;
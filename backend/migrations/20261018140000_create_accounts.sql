@@ -0,0 +1,20 @@
+CREATE TABLE accounts (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    account_type TEXT NOT NULL CHECK (account_type IN ('checking', 'savings', 'credit_card', 'cash', 'investment', 'other')),
+    currency TEXT NOT NULL CHECK (currency ~ '^[A-Z]{3}$'),
+    opening_balance NUMERIC(19, 4) NOT NULL DEFAULT 0,
+    closed BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, name),
+    CONSTRAINT accounts_user_unique UNIQUE (id, user_id)
+);
+
+ALTER TABLE transactions ADD COLUMN account_id INTEGER;
+
+ALTER TABLE transactions ADD CONSTRAINT fk_transaction_account
+    FOREIGN KEY (account_id, user_id)
+    REFERENCES accounts (id, user_id) ON DELETE RESTRICT;
+
+CREATE INDEX idx_transactions_account_id ON transactions (account_id);
@@ -0,0 +1,37 @@
+CREATE TABLE transfers (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    from_account_id INTEGER NOT NULL,
+    to_account_id INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT transfers_user_unique UNIQUE (id, user_id),
+
+    CONSTRAINT fk_transfer_from_account
+        FOREIGN KEY (from_account_id, user_id)
+        REFERENCES accounts (id, user_id) ON DELETE RESTRICT,
+
+    CONSTRAINT fk_transfer_to_account
+        FOREIGN KEY (to_account_id, user_id)
+        REFERENCES accounts (id, user_id) ON DELETE RESTRICT,
+
+    CONSTRAINT no_self_transfer CHECK (from_account_id <> to_account_id)
+);
+
+-- Each transfer is recorded as two legs: money leaving one account and arriving in the other.
+-- Legs have no category, so they never show up in category spending.
+ALTER TABLE transactions ADD COLUMN transfer_id INTEGER;
+
+ALTER TABLE transactions ADD CONSTRAINT fk_transaction_transfer
+    FOREIGN KEY (transfer_id, user_id)
+    REFERENCES transfers (id, user_id) ON DELETE CASCADE;
+
+ALTER TABLE transactions ALTER COLUMN category_id DROP NOT NULL;
+
+ALTER TABLE transactions ADD CONSTRAINT transfer_legs_have_no_category
+    CHECK ((transfer_id IS NULL) = (category_id IS NOT NULL));
+
+ALTER TABLE transactions ADD CONSTRAINT transfer_legs_have_account
+    CHECK (transfer_id IS NULL OR account_id IS NOT NULL);
+
+CREATE INDEX idx_transactions_transfer_id ON transactions (transfer_id);
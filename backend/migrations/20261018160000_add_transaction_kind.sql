@@ -0,0 +1,24 @@
+-- Amounts are signed: money out of the user's pocket is negative, money in is positive.
+-- The kind makes that convention explicit and keeps transfers out of income and spending.
+ALTER TABLE transactions ADD COLUMN kind TEXT;
+
+UPDATE transactions SET kind = CASE
+    WHEN transfer_id IS NOT NULL THEN 'transfer'
+    WHEN amount < 0 THEN 'expense'
+    ELSE 'income'
+END;
+
+ALTER TABLE transactions ALTER COLUMN kind SET NOT NULL;
+
+ALTER TABLE transactions ADD CONSTRAINT transactions_kind_valid
+    CHECK (kind IN ('expense', 'income', 'transfer'));
+
+ALTER TABLE transactions ADD CONSTRAINT transactions_kind_matches_sign
+    CHECK (
+        (kind = 'expense' AND amount <= 0)
+        OR (kind = 'income' AND amount >= 0)
+        OR (kind = 'transfer' AND transfer_id IS NOT NULL)
+    );
+
+ALTER TABLE transactions ADD CONSTRAINT transfer_legs_are_transfers
+    CHECK ((transfer_id IS NULL) = (kind <> 'transfer'));
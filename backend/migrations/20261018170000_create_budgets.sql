@@ -0,0 +1,18 @@
+CREATE TABLE budgets (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL,
+    -- Always the first day of the budgeted month
+    period DATE NOT NULL CHECK (EXTRACT(DAY FROM period) = 1),
+    -- Planned spending in the user's base currency
+    amount NUMERIC(19, 4) NOT NULL CHECK (amount >= 0),
+    -- Carry last month's unspent (or overspent) amount into this one
+    rollover BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT fk_budget_category
+        FOREIGN KEY (category_id, user_id)
+        REFERENCES categories (id, user_id) ON DELETE CASCADE,
+
+    UNIQUE (category_id, period)
+);
@@ -0,0 +1,38 @@
+CREATE TABLE spending_limits (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL,
+    -- Monthly limit in the user's base currency, covering the category and all its subcategories
+    amount NUMERIC(19, 4) NOT NULL CHECK (amount > 0),
+    -- Percentages of the limit that raise a notification when crossed
+    thresholds INTEGER[] NOT NULL DEFAULT '{80,100}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (category_id),
+
+    CONSTRAINT fk_spending_limit_category
+        FOREIGN KEY (category_id, user_id)
+        REFERENCES categories (id, user_id) ON DELETE CASCADE
+);
+
+CREATE TABLE notifications (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL,
+    -- First day of the month the alert is about
+    period DATE NOT NULL,
+    threshold INTEGER NOT NULL,
+    spent NUMERIC(19, 4) NOT NULL,
+    limit_amount NUMERIC(19, 4) NOT NULL,
+    message TEXT NOT NULL,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT fk_notification_category
+        FOREIGN KEY (category_id, user_id)
+        REFERENCES categories (id, user_id) ON DELETE CASCADE,
+
+    -- Each threshold only alerts once per month
+    UNIQUE (category_id, period, threshold)
+);
+
+CREATE INDEX idx_notifications_user_unread ON notifications (user_id, created_at DESC) WHERE read_at IS NULL;
@@ -0,0 +1,37 @@
+CREATE TABLE recurring_transactions (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL,
+    account_id INTEGER,
+    kind TEXT NOT NULL CHECK (kind IN ('expense', 'income')),
+    description TEXT NOT NULL,
+    amount NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL CHECK (currency ~ '^[A-Z]{3}$'),
+    frequency TEXT NOT NULL CHECK (frequency IN ('daily', 'weekly', 'monthly', 'yearly')),
+    -- Every `interval` days/weeks/months/years
+    interval INTEGER NOT NULL DEFAULT 1 CHECK (interval BETWEEN 1 AND 1000),
+    start_date DATE NOT NULL,
+    end_date DATE CHECK (end_date >= start_date),
+    -- Occurrences on or before this date have already been turned into transactions
+    materialized_through DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT recurring_kind_matches_sign
+        CHECK ((kind = 'expense' AND amount <= 0) OR (kind = 'income' AND amount >= 0)),
+
+    CONSTRAINT fk_recurring_category
+        FOREIGN KEY (category_id, user_id)
+        REFERENCES categories (id, user_id) ON DELETE CASCADE,
+
+    CONSTRAINT fk_recurring_account
+        FOREIGN KEY (account_id, user_id)
+        REFERENCES accounts (id, user_id) ON DELETE CASCADE
+);
+
+-- Transactions created from a recurring template remember which occurrence they are,
+-- so the scheduler never creates the same occurrence twice
+ALTER TABLE transactions ADD COLUMN recurring_id INTEGER REFERENCES recurring_transactions(id) ON DELETE SET NULL;
+ALTER TABLE transactions ADD COLUMN occurrence_date DATE;
+
+ALTER TABLE transactions ADD CONSTRAINT transactions_recurring_occurrence_unique
+    UNIQUE (recurring_id, occurrence_date);
@@ -0,0 +1,5 @@
+-- Full-text search over descriptions, kept up to date by Postgres itself
+ALTER TABLE transactions ADD COLUMN search_vector tsvector
+    GENERATED ALWAYS AS (to_tsvector('english', description)) STORED;
+
+CREATE INDEX idx_transactions_search_vector ON transactions USING GIN (search_vector);
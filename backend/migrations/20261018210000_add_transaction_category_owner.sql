@@ -0,0 +1,23 @@
+-- Transactions could point at another user's category. Give the owner a category of the same
+-- name and file those transactions under it, so the stricter constraint below holds.
+INSERT INTO categories (user_id, name)
+SELECT DISTINCT t.user_id, c.name
+FROM transactions t
+JOIN categories c ON c.id = t.category_id
+WHERE c.user_id <> t.user_id
+ON CONFLICT (user_id, name) DO NOTHING;
+
+UPDATE transactions t
+SET category_id = own.id
+FROM categories c, categories own
+WHERE c.id = t.category_id
+AND c.user_id <> t.user_id
+AND own.user_id = t.user_id
+AND own.name = c.name;
+
+-- A transaction's category must belong to the same user, like every other category reference
+ALTER TABLE transactions DROP CONSTRAINT transactions_category_id_fkey;
+
+ALTER TABLE transactions ADD CONSTRAINT fk_transaction_category
+    FOREIGN KEY (category_id, user_id)
+    REFERENCES categories (id, user_id) ON DELETE RESTRICT;
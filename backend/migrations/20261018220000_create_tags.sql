@@ -0,0 +1,39 @@
+-- Free-form labels that cut across categories, e.g. "vacation-2026" or "reimbursable"
+CREATE TABLE tags (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL CHECK (name <> ''),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT tags_user_id_name_key UNIQUE (user_id, name),
+    CONSTRAINT tags_user_unique UNIQUE (id, user_id)
+);
+
+ALTER TABLE transactions ADD CONSTRAINT transactions_user_unique UNIQUE (id, user_id);
+
+CREATE TABLE transaction_tags (
+    transaction_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    user_id UUID NOT NULL,
+
+    PRIMARY KEY (transaction_id, tag_id),
+
+    CONSTRAINT fk_tagged_transaction
+        FOREIGN KEY (transaction_id, user_id)
+        REFERENCES transactions (id, user_id) ON DELETE CASCADE,
+
+    CONSTRAINT fk_transaction_tag
+        FOREIGN KEY (tag_id, user_id)
+        REFERENCES tags (id, user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_transaction_tags_tag_id ON transaction_tags (tag_id);
+
+-- A transaction's tag names in alphabetical order, so every query returning transactions can include them
+CREATE OR REPLACE FUNCTION transaction_tag_names(p_transaction_id INTEGER)
+RETURNS TEXT[] AS $$
+    SELECT COALESCE(array_agg(tg.name ORDER BY tg.name), '{}')
+    FROM transaction_tags tt
+    JOIN tags tg ON tg.id = tt.tag_id
+    WHERE tt.transaction_id = p_transaction_id;
+$$ LANGUAGE sql STABLE;
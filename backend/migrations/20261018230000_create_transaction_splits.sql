@@ -0,0 +1,76 @@
+-- A transaction can be split across several categories, e.g. one receipt covering groceries and household.
+-- The transaction keeps its own category_id; once it has splits, those decide where the money counts.
+CREATE TABLE transaction_splits (
+    id SERIAL PRIMARY KEY,
+    transaction_id INTEGER NOT NULL,
+    user_id UUID NOT NULL,
+    category_id INTEGER NOT NULL,
+    amount NUMERIC(19, 4) NOT NULL,
+    memo TEXT,
+
+    CONSTRAINT fk_split_transaction
+        FOREIGN KEY (transaction_id, user_id)
+        REFERENCES transactions (id, user_id) ON DELETE CASCADE,
+
+    CONSTRAINT fk_split_category
+        FOREIGN KEY (category_id, user_id)
+        REFERENCES categories (id, user_id) ON DELETE RESTRICT
+);
+
+CREATE INDEX idx_transaction_splits_transaction_id ON transaction_splits (transaction_id);
+CREATE INDEX idx_transaction_splits_category_id ON transaction_splits (category_id);
+
+CREATE OR REPLACE FUNCTION check_split_total()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_transaction_id INTEGER;
+    v_amount NUMERIC;
+    v_split_total NUMERIC;
+BEGIN
+    IF TG_TABLE_NAME = 'transactions' THEN
+        v_transaction_id := NEW.id;
+    ELSIF TG_OP = 'DELETE' THEN
+        v_transaction_id := OLD.transaction_id;
+    ELSE
+        v_transaction_id := NEW.transaction_id;
+    END IF;
+
+    SELECT amount INTO v_amount FROM transactions WHERE id = v_transaction_id;
+
+    -- The transaction itself was deleted, taking its splits with it
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    SELECT SUM(amount) INTO v_split_total FROM transaction_splits WHERE transaction_id = v_transaction_id;
+
+    IF v_split_total IS NOT NULL AND v_split_total <> v_amount THEN
+        RAISE EXCEPTION 'Splits add up to % but the transaction amount is %', v_split_total, v_amount;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Checked at commit, so splits and the amount can be rewritten one statement at a time
+CREATE CONSTRAINT TRIGGER trg_split_total
+AFTER INSERT OR UPDATE OR DELETE ON transaction_splits
+DEFERRABLE INITIALLY DEFERRED
+FOR EACH ROW EXECUTE FUNCTION check_split_total();
+
+CREATE CONSTRAINT TRIGGER trg_split_total_on_amount_change
+AFTER UPDATE OF amount ON transactions
+DEFERRABLE INITIALLY DEFERRED
+FOR EACH ROW EXECUTE FUNCTION check_split_total();
+
+-- Money as it counts towards categories: one row per split, or the transaction itself when it isn't split.
+-- Transfer legs have no category and are left out.
+CREATE VIEW categorized_transactions AS
+SELECT t.id AS transaction_id, t.user_id, s.category_id, s.amount, t.currency, t.kind, t.created_at
+FROM transactions t
+JOIN transaction_splits s ON s.transaction_id = t.id
+UNION ALL
+SELECT t.id, t.user_id, t.category_id, t.amount, t.currency, t.kind, t.created_at
+FROM transactions t
+WHERE t.category_id IS NOT NULL
+AND NOT EXISTS (SELECT 1 FROM transaction_splits s WHERE s.transaction_id = t.id);
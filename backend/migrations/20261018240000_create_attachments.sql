@@ -0,0 +1,17 @@
+-- Receipts and other documents kept with a transaction. The file itself lives in attachment storage under storage_key.
+CREATE TABLE attachments (
+    id SERIAL PRIMARY KEY,
+    transaction_id INTEGER NOT NULL,
+    user_id UUID NOT NULL,
+    file_name TEXT NOT NULL CHECK (file_name <> ''),
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes > 0),
+    storage_key TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT fk_attachment_transaction
+        FOREIGN KEY (transaction_id, user_id)
+        REFERENCES transactions (id, user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_attachments_transaction_id ON attachments (transaction_id);
@@ -0,0 +1,29 @@
+-- How to read one bank's CSV export. Columns are zero-based positions in each row.
+CREATE TABLE csv_profiles (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL CHECK (name <> ''),
+    has_header BOOLEAN NOT NULL DEFAULT TRUE,
+    delimiter TEXT NOT NULL DEFAULT ',' CHECK (length(delimiter) = 1),
+    date_column INTEGER NOT NULL CHECK (date_column >= 0),
+    date_format TEXT NOT NULL,
+    description_column INTEGER NOT NULL CHECK (description_column >= 0),
+    -- Either a single signed amount column, or separate debit and credit columns
+    amount_column INTEGER CHECK (amount_column >= 0),
+    debit_column INTEGER CHECK (debit_column >= 0),
+    credit_column INTEGER CHECK (credit_column >= 0),
+    decimal_separator TEXT NOT NULL DEFAULT '.' CHECK (decimal_separator IN ('.', ',')),
+    negate BOOLEAN NOT NULL DEFAULT FALSE,
+    default_category_id INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT csv_profiles_user_id_name_key UNIQUE (user_id, name),
+
+    CONSTRAINT csv_profile_amount_source
+        CHECK ((amount_column IS NULL) <> (debit_column IS NULL AND credit_column IS NULL)),
+
+    -- Deleting the category only clears the default, the profile stays usable for previews
+    CONSTRAINT fk_csv_profile_category
+        FOREIGN KEY (default_category_id, user_id)
+        REFERENCES categories (id, user_id) ON DELETE SET NULL (default_category_id)
+);
@@ -0,0 +1,5 @@
+-- Identifies a transaction in the bank's records (e.g. an OFX FITID), so importing the same statement twice is harmless
+ALTER TABLE transactions ADD COLUMN external_id TEXT;
+
+-- NULLs never conflict, so transactions entered by hand are unaffected
+ALTER TABLE transactions ADD CONSTRAINT transactions_user_id_external_id_key UNIQUE (user_id, external_id);
@@ -0,0 +1,50 @@
+-- Splits must also have the sign of the transaction's kind. Otherwise an expense of -10 could be
+-- split into +40 and -50, and the +40 would count as income in every per-category total.
+CREATE OR REPLACE FUNCTION check_split_total()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_transaction_id INTEGER;
+    v_amount NUMERIC;
+    v_kind TEXT;
+    v_split_total NUMERIC;
+BEGIN
+    IF TG_TABLE_NAME = 'transactions' THEN
+        v_transaction_id := NEW.id;
+    ELSIF TG_OP = 'DELETE' THEN
+        v_transaction_id := OLD.transaction_id;
+    ELSE
+        v_transaction_id := NEW.transaction_id;
+    END IF;
+
+    SELECT amount, kind INTO v_amount, v_kind FROM transactions WHERE id = v_transaction_id;
+
+    -- The transaction itself was deleted, taking its splits with it
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    SELECT SUM(amount) INTO v_split_total FROM transaction_splits WHERE transaction_id = v_transaction_id;
+
+    IF v_split_total IS NOT NULL AND v_split_total <> v_amount THEN
+        RAISE EXCEPTION 'Splits add up to % but the transaction amount is %', v_split_total, v_amount;
+    END IF;
+
+    IF EXISTS (
+        SELECT 1 FROM transaction_splits
+        WHERE transaction_id = v_transaction_id
+        AND ((v_kind = 'expense' AND amount > 0) OR (v_kind = 'income' AND amount < 0))
+    ) THEN
+        RAISE EXCEPTION 'Every split of an % must have the same sign as the transaction', v_kind;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- A change of kind can leave the splits with the wrong sign too
+DROP TRIGGER trg_split_total_on_amount_change ON transactions;
+
+CREATE CONSTRAINT TRIGGER trg_split_total_on_amount_change
+AFTER UPDATE OF amount, kind ON transactions
+DEFERRABLE INITIALLY DEFERRED
+FOR EACH ROW EXECUTE FUNCTION check_split_total();
@@ -0,0 +1,7 @@
+-- Deleting an account must not silently drop the recurring transactions that feed it,
+-- the same way it can't drop the account's transactions
+ALTER TABLE recurring_transactions DROP CONSTRAINT fk_recurring_account;
+
+ALTER TABLE recurring_transactions ADD CONSTRAINT fk_recurring_account
+    FOREIGN KEY (account_id, user_id)
+    REFERENCES accounts (id, user_id) ON DELETE RESTRICT;
@@ -0,0 +1,38 @@
+-- Pairs each of the user's categories with itself and every category above it, so totals can be rolled up
+-- into parents. A function rather than a view so the recursion only walks one user's categories.
+CREATE FUNCTION category_ancestry(p_user_id UUID)
+RETURNS TABLE (category_id INTEGER, ancestor_id INTEGER) AS $$
+    WITH RECURSIVE ancestry AS (
+        -- Base case: every category counts towards itself
+        SELECT c.id AS category_id, c.id AS ancestor_id FROM categories c WHERE c.user_id = p_user_id
+        UNION ALL
+        -- Recursive step: and towards every category above it
+        SELECT a.category_id, ch.parent_id
+        FROM ancestry a
+        INNER JOIN category_hierarchy ch ON ch.category_id = a.ancestor_id
+    )
+    SELECT category_id, ancestor_id FROM ancestry;
+$$ LANGUAGE sql STABLE;
+
+-- Transactions with their amount in the owner's base currency. base_amount is NULL when no rate is known.
+CREATE VIEW converted_transactions AS
+SELECT
+    t.id AS transaction_id,
+    t.user_id,
+    t.kind,
+    t.created_at,
+    t.amount * exchange_rate_on(t.user_id, t.currency, u.base_currency, t.created_at) AS base_amount
+FROM transactions t
+JOIN users u ON u.id = t.user_id;
+
+-- categorized_transactions with their amount in the owner's base currency, for per-category totals
+CREATE VIEW converted_categorized_transactions AS
+SELECT
+    t.transaction_id,
+    t.user_id,
+    t.category_id,
+    t.kind,
+    t.created_at,
+    t.amount * exchange_rate_on(t.user_id, t.currency, u.base_currency, t.created_at) AS base_amount
+FROM categorized_transactions t
+JOIN users u ON u.id = t.user_id;
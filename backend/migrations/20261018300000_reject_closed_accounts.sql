@@ -0,0 +1,40 @@
+-- A closed account keeps its history but takes no new money. Moving or adding anything into one is
+-- refused; rows already in it can still be edited. The column to check and the constraint name
+-- reported to the client are the trigger's arguments.
+CREATE OR REPLACE FUNCTION check_account_open()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_account_id INTEGER := (to_jsonb(NEW) ->> TG_ARGV[0])::int;
+BEGIN
+    IF v_account_id IS NULL THEN
+        RETURN NEW;
+    END IF;
+
+    IF TG_OP = 'UPDATE' AND v_account_id = (to_jsonb(OLD) ->> TG_ARGV[0])::int THEN
+        RETURN NEW;
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM accounts WHERE id = v_account_id AND closed) THEN
+        RAISE EXCEPTION 'Account % is closed', v_account_id
+            USING ERRCODE = 'check_violation', CONSTRAINT = TG_ARGV[1];
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_transaction_account_open
+BEFORE INSERT OR UPDATE OF account_id ON transactions
+FOR EACH ROW EXECUTE FUNCTION check_account_open('account_id', 'transaction_account_open');
+
+CREATE TRIGGER trg_recurring_account_open
+BEFORE INSERT OR UPDATE OF account_id ON recurring_transactions
+FOR EACH ROW EXECUTE FUNCTION check_account_open('account_id', 'recurring_account_open');
+
+CREATE TRIGGER trg_transfer_from_account_open
+BEFORE INSERT OR UPDATE OF from_account_id ON transfers
+FOR EACH ROW EXECUTE FUNCTION check_account_open('from_account_id', 'transfer_from_account_open');
+
+CREATE TRIGGER trg_transfer_to_account_open
+BEFORE INSERT OR UPDATE OF to_account_id ON transfers
+FOR EACH ROW EXECUTE FUNCTION check_account_open('to_account_id', 'transfer_to_account_open');
@@ -0,0 +1,66 @@
+use sqlx::{types::time::OffsetDateTime, PgExecutor};
+use uuid::Uuid;
+
+/// Checks the user's spending limits for every month in `changed_at` (the dates of the transactions
+/// that were written) and records a notification for every threshold that has been crossed.
+/// Thresholds that already alerted for a month are left alone, so this is safe to call after any
+/// change to the user's transactions. Returns the number of new alerts.
+///
+/// Run it on the transaction that made the change, before committing, so a failure here can't
+/// report an error for a write that was actually saved.
+pub async fn evaluate_spending_limits<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    changed_at: &[OffsetDateTime],
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        WITH RECURSIVE category_tree AS (
+            -- Base case: every category with a limit
+            SELECT category_id AS root_id, category_id AS id
+            FROM spending_limits
+            WHERE user_id = $1
+            UNION ALL
+            -- Recursive step: find all children of the categories in the tree
+            SELECT ct.root_id, ch.category_id
+            FROM category_hierarchy ch
+            INNER JOIN category_tree ct ON ch.parent_id = ct.id
+        ),
+        months AS (
+            SELECT DISTINCT date_trunc('month', changed_at AT TIME ZONE 'UTC')::date AS period
+            FROM unnest($2::timestamptz[]) AS changed_at
+        ),
+        spending AS (
+            SELECT ct.root_id, m.period, -SUM(t.base_amount) AS spent
+            FROM converted_categorized_transactions t
+            JOIN category_tree ct ON t.category_id = ct.id
+            JOIN months m ON date_trunc('month', t.created_at AT TIME ZONE 'UTC')::date = m.period
+            WHERE t.user_id = $1
+            AND t.kind = 'expense'
+            GROUP BY ct.root_id, m.period
+        )
+        INSERT INTO notifications (user_id, category_id, period, threshold, spent, limit_amount, message)
+        SELECT
+            $1,
+            l.category_id,
+            s.period,
+            th.threshold,
+            ROUND(s.spent, 4),
+            l.amount,
+            format('Spending in %s has reached %s%% of its monthly limit', c.name, th.threshold)
+        FROM spending_limits l
+        JOIN categories c ON l.category_id = c.id
+        JOIN spending s ON s.root_id = l.category_id
+        CROSS JOIN LATERAL unnest(l.thresholds) AS th(threshold)
+        WHERE l.user_id = $1
+        AND s.spent * 100 >= l.amount * th.threshold
+        ON CONFLICT (category_id, period, threshold) DO NOTHING
+        "#,
+        user_id,
+        changed_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,291 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+use crate::{
+    models::{csv_import::{CsvImportPreview, CsvMapping, CsvRow, CsvRowError}, transaction::TransactionKind},
+    money::Money
+};
+
+/// Splits CSV text into records, returning each with the line it starts on.
+/// Quoted fields may contain the delimiter, doubled quotes and line breaks. Blank lines are skipped.
+fn parse_records(input: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>, CsvRowError> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|value| !value.trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            }
+            _ if c == delimiter => record.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(CsvRowError { line: record_line, message: "Unterminated quoted field".to_string() });
+    }
+
+    record.push(field);
+    if record.iter().any(|value| !value.trim().is_empty()) {
+        records.push((record_line, record));
+    }
+
+    Ok(records)
+}
+
+/// Reads an amount the way banks write them: `1,234.56`, `1.234,56`, `$ 12.00`, `(12.00)` or `12.00-`.
+/// Returns `None` for an empty cell.
+fn parse_amount(raw: &str, decimal_separator: char) -> Result<Option<Money>, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+
+    let thousands_separator = if decimal_separator == ',' { '.' } else { ',' };
+    let mut negative = false;
+    let mut digits = String::new();
+
+    for c in raw.chars() {
+        match c {
+            '0'..='9' => digits.push(c),
+            '-' | '(' | ')' => negative = true,
+            _ if c == decimal_separator => digits.push('.'),
+            // Thousands separators, spaces, a leading plus and currency symbols carry no value
+            _ if c == thousands_separator || c == '+' || c.is_whitespace() || !c.is_alphanumeric() => {}
+            _ => return Err(format!("\"{}\" is not an amount", raw)),
+        }
+    }
+
+    if digits.is_empty() {
+        return Err(format!("\"{}\" is not an amount", raw));
+    }
+    if negative {
+        digits.insert(0, '-');
+    }
+
+    Money::from_str(&digits)
+        .map(Some)
+        .map_err(|err| format!("\"{}\": {}", raw, err))
+}
+
+fn parse_date(raw: &str, format: &str) -> Result<DateTime<Utc>, String> {
+    let raw = raw.trim();
+
+    // Formats with a time of day keep it, plain dates are taken as midnight UTC
+    NaiveDateTime::parse_from_str(raw, format)
+        .or_else(|_| NaiveDate::parse_from_str(raw, format).map(|date| date.and_time(NaiveTime::MIN)))
+        .map(|datetime| datetime.and_utc())
+        .map_err(|_| format!("\"{}\" doesn't match the date format {}", raw, format))
+}
+
+fn negate(amount: &Money) -> Money {
+    Money::from(-amount.as_decimal().clone())
+}
+
+fn parse_row(fields: &[String], mapping: &CsvMapping) -> Result<(DateTime<Utc>, String, Money), String> {
+    let required = |column: usize| {
+        fields
+            .get(column)
+            .map(String::as_str)
+            .ok_or_else(|| format!("Column {} is missing", column + 1))
+    };
+    // Exports often leave out empty trailing columns
+    let optional = |column: Option<usize>| {
+        column
+            .and_then(|column| fields.get(column))
+            .map(|raw| parse_amount(raw, mapping.decimal_separator))
+            .transpose()
+            .map(Option::flatten)
+    };
+
+    let created_at = parse_date(required(mapping.date_column)?, &mapping.date_format)?;
+
+    let description = required(mapping.description_column)?.trim().to_string();
+    if description.is_empty() {
+        return Err("Description is empty".to_string());
+    }
+
+    let amount = match mapping.amount_column {
+        Some(column) => parse_amount(required(column)?, mapping.decimal_separator)?
+            .ok_or_else(|| "Amount is empty".to_string())?,
+        None => match (optional(mapping.debit_column)?, optional(mapping.credit_column)?) {
+            (Some(debit), None) => negate(&debit.abs()),
+            (None, Some(credit)) => credit.abs(),
+            // Some banks fill the unused column with zero
+            (Some(debit), Some(credit)) => Money::from(credit.abs().as_decimal() - debit.abs().as_decimal()),
+            (None, None) => return Err("Both debit and credit are empty".to_string()),
+        },
+    };
+
+    let amount = if mapping.negate { negate(&amount) } else { amount };
+
+    Ok((created_at, description, amount))
+}
+
+/// Parses a bank's CSV export with `mapping`. Rows that can't be read are reported in `errors`
+/// instead of stopping the whole file, so a preview can show everything that's wrong at once.
+pub fn parse_csv(input: &str, mapping: &CsvMapping) -> CsvImportPreview {
+    let mut preview = CsvImportPreview { columns: Vec::new(), rows: Vec::new(), errors: Vec::new() };
+
+    let mut records = match parse_records(input, mapping.delimiter) {
+        Ok(records) => records.into_iter(),
+        Err(err) => {
+            preview.errors.push(err);
+            return preview;
+        }
+    };
+
+    if mapping.has_header && let Some((_, header)) = records.next() {
+        preview.columns = header.into_iter().map(|column| column.trim().to_string()).collect();
+    }
+
+    for (line, fields) in records {
+        match parse_row(&fields, mapping) {
+            Ok((created_at, description, amount)) => preview.rows.push(CsvRow {
+                line,
+                created_at,
+                description,
+                kind: TransactionKind::from_amount(&amount),
+                amount,
+            }),
+            Err(message) => preview.errors.push(CsvRowError { line, message }),
+        }
+    }
+
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> CsvMapping {
+        CsvMapping {
+            has_header: true,
+            delimiter: ',',
+            date_column: 0,
+            date_format: "%Y-%m-%d".to_string(),
+            description_column: 1,
+            amount_column: Some(2),
+            debit_column: None,
+            credit_column: None,
+            decimal_separator: '.',
+            negate: false,
+        }
+    }
+
+    fn amounts(preview: &CsvImportPreview) -> Vec<String> {
+        preview.rows.iter().map(|row| row.amount.to_string()).collect()
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        let input = "\u{feff}a,b\r\n\"x, y\",\"say \"\"hi\"\"\"\r\n\r\n\"multi\nline\",z\n";
+        let records = parse_records(input, ',').unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1], (2, vec!["x, y".to_string(), "say \"hi\"".to_string()]));
+        assert_eq!(records[2], (4, vec!["multi\nline".to_string(), "z".to_string()]));
+        assert!(parse_records("a,\"b\n", ',').is_err());
+    }
+
+    #[test]
+    fn test_amount_formats() {
+        let parse = |raw, separator| parse_amount(raw, separator).unwrap().map(|amount| amount.to_string());
+
+        assert_eq!(parse("1,234.56", '.'), Some("1234.56".to_string()));
+        assert_eq!(parse("-1.234,56", ','), Some("-1234.56".to_string()));
+        assert_eq!(parse("$ 12.00", '.'), Some("12".to_string()));
+        assert_eq!(parse("(12.50)", '.'), Some("-12.5".to_string()));
+        assert_eq!(parse("12.50-", '.'), Some("-12.5".to_string()));
+        assert_eq!(parse("  ", '.'), None);
+        assert!(parse_amount("twelve", '.').is_err());
+        assert!(parse_amount("1.23456", '.').is_err());
+    }
+
+    #[test]
+    fn test_signed_amount_column() {
+        let input = "Date,Description,Amount\n2026-10-01,Coffee,-4.50\n2026-10-02,Salary,2000\n";
+        let preview = parse_csv(input, &mapping());
+
+        assert_eq!(preview.columns, vec!["Date", "Description", "Amount"]);
+        assert_eq!(amounts(&preview), vec!["-4.5", "2000"]);
+        assert!(preview.rows[0].kind == TransactionKind::Expense);
+        assert!(preview.rows[1].kind == TransactionKind::Income);
+        assert_eq!(preview.rows[1].line, 3);
+        assert!(preview.errors.is_empty());
+    }
+
+    #[test]
+    fn test_debit_and_credit_columns() {
+        let mapping = CsvMapping {
+            has_header: false,
+            delimiter: ';',
+            date_format: "%d/%m/%Y".to_string(),
+            amount_column: None,
+            debit_column: Some(2),
+            credit_column: Some(3),
+            decimal_separator: ',',
+            ..mapping()
+        };
+        let input = "01/10/2026;Groceries;45,10;\n02/10/2026;Refund;0,00;5,00\n03/10/2026;Nothing;;\n";
+        let preview = parse_csv(input, &mapping);
+
+        assert_eq!(amounts(&preview), vec!["-45.1", "5"]);
+        assert_eq!(preview.rows[0].created_at.to_rfc3339(), "2026-10-01T00:00:00+00:00");
+        assert_eq!(preview.errors, vec![CsvRowError { line: 3, message: "Both debit and credit are empty".to_string() }]);
+    }
+
+    #[test]
+    fn test_negate() {
+        let mapping = CsvMapping { negate: true, ..mapping() };
+        let preview = parse_csv("Date,Description,Amount\n2026-10-01,Card purchase,19.99\n", &mapping);
+
+        assert_eq!(amounts(&preview), vec!["-19.99"]);
+    }
+
+    #[test]
+    fn test_row_errors_report_line() {
+        let input = "Date,Description,Amount\n10/01/2026,Coffee,-4.50\n2026-10-02,,1\n2026-10-03,Short\n";
+        let preview = parse_csv(input, &mapping());
+
+        assert!(preview.rows.is_empty());
+        let lines: Vec<usize> = preview.errors.iter().map(|err| err.line).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+        assert_eq!(preview.errors[2].message, "Column 3 is missing");
+    }
+}
@@ -0,0 +1,70 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An ISO 4217 alphabetic currency code, e.g. `CAD`. Always stored upper case.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Currency(String);
+
+impl Currency {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Currency {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let code = value.trim().to_ascii_uppercase();
+        if code.len() != 3 || !code.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err("Currency must be a three letter ISO 4217 code");
+        }
+        Ok(Currency(code))
+    }
+}
+
+/// Values read back from the database already passed the column's CHECK constraint.
+impl From<String> for Currency {
+    fn from(value: String) -> Self {
+        Currency(value)
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_case() {
+        assert_eq!("usd".parse::<Currency>().unwrap().as_str(), "USD");
+        assert_eq!(" eur ".parse::<Currency>().unwrap().as_str(), "EUR");
+    }
+
+    #[test]
+    fn test_rejects_invalid_codes() {
+        assert!("US".parse::<Currency>().is_err());
+        assert!("USDT".parse::<Currency>().is_err());
+        assert!("U$D".parse::<Currency>().is_err());
+        assert!("".parse::<Currency>().is_err());
+    }
+}
@@ -0,0 +1,31 @@
+use std::borrow::Cow;
+
+use encoding_rs::WINDOWS_1252;
+
+/// Reads an uploaded bank file as text. OFX 1.x statements and CSV exports are often Windows-1252
+/// rather than UTF-8, so anything that isn't valid UTF-8 is read as Windows-1252.
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(bytes) {
+        // Spreadsheet programs start UTF-8 files with a byte order mark
+        Ok(text) => Cow::Borrowed(text.strip_prefix('\u{feff}').unwrap_or(text)),
+        Err(_) => WINDOWS_1252.decode_without_bom_handling(bytes).0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_utf8_as_is() {
+        assert_eq!(decode("Café €5".as_bytes()), "Café €5");
+        assert_eq!(decode("\u{feff}date,amount".as_bytes()), "date,amount");
+    }
+
+    #[test]
+    fn test_reads_windows_1252_punctuation() {
+        // "Café – “5 €” at Bob’s" as Windows-1252
+        let bytes = b"Caf\xe9 \x96 \x935 \x80\x94 at Bob\x92s";
+        assert_eq!(decode(bytes), "Café – “5 €” at Bob’s");
+    }
+}
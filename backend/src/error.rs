@@ -0,0 +1,211 @@
+use axum::{http::StatusCode, response::{IntoResponse, Response}, Json};
+use serde::Serialize;
+use sqlx::error::ErrorKind;
+
+/// An error returned by a handler. Every error response has the same JSON shape:
+/// `{ "code": "not_found", "message": "Category not found", "details": [{ "field": "category_id", "message": "..." }] }`
+#[derive(Debug)]
+pub struct AppError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Vec<FieldError>,
+}
+
+/// Points an error at one field of the request.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'static str,
+    message: &'a str,
+    details: &'a [FieldError],
+}
+
+impl AppError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> AppError {
+        AppError { status, code, message: message.into(), details: Vec::new() }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> AppError {
+        AppError::new(StatusCode::BAD_REQUEST, "bad_request", message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> AppError {
+        AppError::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> AppError {
+        AppError::new(StatusCode::FORBIDDEN, "forbidden", message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> AppError {
+        AppError::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> AppError {
+        AppError::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> AppError {
+        AppError::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", message)
+    }
+
+    /// The request was well-formed but its contents don't make sense.
+    pub fn validation(message: impl Into<String>) -> AppError {
+        AppError::new(StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", message)
+    }
+
+    pub fn internal() -> AppError {
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Something went wrong")
+    }
+
+    /// Attaches a message about a specific request field.
+    pub fn with_field(mut self, field: &'static str, message: impl Into<String>) -> AppError {
+        self.details.push(FieldError { field, message: message.into() });
+        self
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.code,
+            message: &self.message,
+            details: &self.details,
+        };
+
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// The request field a constraint protects, and what to tell the client when it's violated.
+fn constraint_field(constraint: &str) -> Option<(&'static str, &'static str)> {
+    let field = match constraint {
+        "users_username_key" => ("username", "Username is already taken"),
+        "categories_user_id_name_key" => ("name", "A category with this name already exists"),
+        "accounts_user_id_name_key" => ("name", "An account with this name already exists"),
+        "api_tokens_user_id_name_key" => ("name", "A token with this name already exists"),
+        "tags_user_id_name_key" => ("name", "A tag with this name already exists"),
+        "csv_profiles_user_id_name_key" => ("name", "A CSV profile with this name already exists"),
+        "budgets_category_id_period_key" => ("month", "This category already has a budget for the month"),
+        "fk_transaction_category" | "fk_budget_category" | "fk_spending_limit_category" | "fk_recurring_category" => {
+            ("category_id", "Category not found")
+        }
+        "fk_split_category" => ("splits", "Category not found"),
+        "fk_csv_profile_category" => ("default_category_id", "Category not found"),
+        "fk_transaction_account" | "fk_recurring_account" => ("account_id", "Account not found"),
+        "fk_transfer_from_account" => ("from_account_id", "Account not found"),
+        "fk_transfer_to_account" => ("to_account_id", "Account not found"),
+        "fk_parent_user" => ("parent_id", "Parent category not found"),
+        "transaction_account_open" | "recurring_account_open" => ("account_id", "Account is closed"),
+        "transfer_from_account_open" => ("from_account_id", "Account is closed"),
+        "transfer_to_account_open" => ("to_account_id", "Account is closed"),
+        "transactions_kind_matches_sign" | "recurring_kind_matches_sign" => {
+            ("amount", "Expenses must be negative and income positive")
+        }
+        "transfer_legs_have_no_category" => ("category_id", "Income and expenses need a category"),
+        "no_self_parenting" => ("parent_id", "A category cannot be its own parent"),
+        "no_self_transfer" => ("to_account_id", "Cannot transfer to the same account"),
+        _ => return None,
+    };
+
+    Some(field)
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> AppError {
+        if let sqlx::Error::RowNotFound = err {
+            return AppError::not_found("Not found");
+        }
+
+        let Some(db_err) = err.as_database_error() else {
+            eprintln!("Database error: {}", err);
+            return AppError::internal();
+        };
+
+        let field = db_err.constraint().and_then(constraint_field);
+        let error = match db_err.kind() {
+            ErrorKind::UniqueViolation => AppError::conflict("Already exists"),
+            // Deleting something that's still referenced, as opposed to referencing something that doesn't exist
+            ErrorKind::ForeignKeyViolation if db_err.message().starts_with("update or delete") => {
+                return AppError::conflict("Still in use by other records");
+            }
+            ErrorKind::ForeignKeyViolation => AppError::validation("Refers to a record that doesn't exist"),
+            ErrorKind::CheckViolation | ErrorKind::NotNullViolation => AppError::validation("Invalid value"),
+            // Raised by our own triggers, with a message meant for the client
+            _ if db_err.code().as_deref() == Some("P0001") => return AppError::validation(db_err.message()),
+            _ => {
+                eprintln!("Database error: {}", err);
+                return AppError::internal();
+            }
+        };
+
+        match field {
+            Some((field, message)) => AppError { message: message.to_string(), ..error }.with_field(field, message),
+            None => error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_json(error: AppError) -> (StatusCode, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_serializes_stable_shape() {
+        let (status, body) = body_json(AppError::not_found("Category not found")).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, serde_json::json!({
+            "code": "not_found",
+            "message": "Category not found",
+            "details": [],
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_includes_field_details() {
+        let error = AppError::validation("Invalid transaction").with_field("amount", "Must be negative");
+        let (status, body) = body_json(error).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["details"], serde_json::json!([{ "field": "amount", "message": "Must be negative" }]));
+    }
+
+    #[test]
+    fn test_maps_known_constraints_to_fields() {
+        assert_eq!(constraint_field("categories_user_id_name_key").map(|(f, _)| f), Some("name"));
+        assert_eq!(constraint_field("fk_transaction_account").map(|(f, _)| f), Some("account_id"));
+        assert_eq!(constraint_field("fk_transaction_category").map(|(f, _)| f), Some("category_id"));
+        assert_eq!(constraint_field("something_else"), None);
+    }
+
+    #[test]
+    fn test_maps_closed_account_checks_to_account_fields() {
+        assert_eq!(constraint_field("transaction_account_open"), Some(("account_id", "Account is closed")));
+        assert_eq!(constraint_field("recurring_account_open"), Some(("account_id", "Account is closed")));
+        assert_eq!(constraint_field("transfer_from_account_open").map(|(f, _)| f), Some("from_account_id"));
+        assert_eq!(constraint_field("transfer_to_account_open").map(|(f, _)| f), Some("to_account_id"));
+    }
+
+    #[tokio::test]
+    async fn test_row_not_found_is_404() {
+        let (status, body) = body_json(AppError::from(sqlx::Error::RowNotFound)).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{currency::Currency, models::exchange_rate::ExchangeRate, time_conversion::{convert_chrono_to_date, convert_chrono_to_time}};
+
+/// ECB reference rates are all quoted against the euro.
+const ECB_BASE_CURRENCY: &str = "EUR";
+
+/// Finds the rate converting `from` into `to`, using the nearest rate on or before `at`.
+/// The lookup itself lives in the `exchange_rate_on` SQL function so queries can convert in bulk.
+pub async fn find_rate(
+    pool: &PgPool,
+    user_id: Uuid,
+    from: &Currency,
+    to: &Currency,
+    at: DateTime<Utc>,
+) -> Result<Option<BigDecimal>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT exchange_rate_on($1, $2, $3, $4) AS rate"#,
+        user_id,
+        from.as_str(),
+        to.as_str(),
+        convert_chrono_to_time(at)
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Inserts or replaces rates in a single statement. Later duplicates in `rates` win.
+pub async fn upsert_rates(pool: &PgPool, user_id: Uuid, rates: Vec<ExchangeRate>) -> Result<usize, sqlx::Error> {
+    let mut unique = BTreeMap::new();
+    for rate in rates {
+        let key = (rate.date, rate.from_currency.to_string(), rate.to_currency.to_string());
+        unique.insert(key, rate.rate);
+    }
+
+    let count = unique.len();
+    let mut dates = Vec::with_capacity(count);
+    let mut from_currencies = Vec::with_capacity(count);
+    let mut to_currencies = Vec::with_capacity(count);
+    let mut values = Vec::with_capacity(count);
+
+    for ((date, from, to), rate) in unique {
+        dates.push(convert_chrono_to_date(date));
+        from_currencies.push(from);
+        to_currencies.push(to);
+        values.push(rate.as_decimal().clone());
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO exchange_rates (user_id, rate_date, from_currency, to_currency, rate)
+        SELECT $1, * FROM UNNEST($2::date[], $3::text[], $4::text[], $5::numeric[])
+        ON CONFLICT (user_id, rate_date, from_currency, to_currency)
+        DO UPDATE SET rate = EXCLUDED.rate
+        "#,
+        user_id,
+        &dates,
+        &from_currencies,
+        &to_currencies,
+        &values
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Parses rates from CSV. Two layouts are accepted:
+/// - long: a `date,from,to,rate` header followed by one rate per line
+/// - wide (ECB `eurofxref` CSV): a `Date,USD,JPY,...` header, each row giving EUR rates for one day
+pub fn parse_rates_csv(input: &str) -> Result<Vec<ExchangeRate>, String> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+
+    let header: Vec<String> = header.split(',').map(|col| col.trim().to_ascii_lowercase()).collect();
+
+    if header == ["date", "from", "to", "rate"] {
+        let mut rates = Vec::new();
+        for (line_no, line) in lines {
+            let cols: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [date, from, to, rate] = cols[..] else {
+                return Err(format!("Line {}: expected 4 columns", line_no));
+            };
+            rates.push(ExchangeRate {
+                date: parse_date(date).map_err(|e| format!("Line {}: {}", line_no, e))?,
+                from_currency: from.parse().map_err(|e| format!("Line {}: {}", line_no, e))?,
+                to_currency: to.parse().map_err(|e| format!("Line {}: {}", line_no, e))?,
+                rate: rate.parse().map_err(|e| format!("Line {}: {}", line_no, e))?,
+            });
+        }
+        return Ok(rates);
+    }
+
+    if header.first().map(String::as_str) != Some("date") {
+        return Err("Unrecognized CSV header, expected date,from,to,rate or Date,<currency>,...".to_string());
+    }
+
+    // Wide layout: every other header column is a currency code (ECB files end with a trailing comma)
+    let currencies: Vec<Option<Currency>> = header[1..]
+        .iter()
+        .map(|col| if col.is_empty() { Ok(None) } else { col.parse().map(Some) })
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Line 1: {}", e))?;
+
+    let base: Currency = ECB_BASE_CURRENCY.parse().expect("Valid currency");
+    let mut rates = Vec::new();
+
+    for (line_no, line) in lines {
+        let mut cols = line.split(',').map(str::trim);
+        let date = parse_date(cols.next().unwrap_or_default()).map_err(|e| format!("Line {}: {}", line_no, e))?;
+
+        for (currency, value) in currencies.iter().zip(cols) {
+            let Some(currency) = currency else { continue };
+            // ECB marks currencies that weren't quoted that day as N/A
+            if value.is_empty() || value.eq_ignore_ascii_case("n/a") {
+                continue;
+            }
+            rates.push(ExchangeRate {
+                date,
+                from_currency: base.clone(),
+                to_currency: currency.clone(),
+                rate: value.parse().map_err(|e| format!("Line {}: {}", line_no, e))?,
+            });
+        }
+    }
+
+    Ok(rates)
+}
+
+/// Parses an ECB-style `eurofxref` XML document:
+/// `<Cube time="2024-01-02"><Cube currency="USD" rate="1.0956"/>...</Cube>`
+pub fn parse_ecb_xml(input: &str) -> Result<Vec<ExchangeRate>, String> {
+    let base: Currency = ECB_BASE_CURRENCY.parse().expect("Valid currency");
+    let mut rates = Vec::new();
+    let mut current_date: Option<NaiveDate> = None;
+
+    for tag in input.split('<').skip(1) {
+        let Some(tag) = tag.split('>').next() else { continue };
+        if !tag.starts_with("Cube") {
+            continue;
+        }
+
+        if let Some(time) = xml_attribute(tag, "time") {
+            current_date = Some(parse_date(time)?);
+        }
+
+        if let (Some(currency), Some(rate)) = (xml_attribute(tag, "currency"), xml_attribute(tag, "rate")) {
+            let Some(date) = current_date else {
+                return Err("Rate found outside of a dated Cube element".to_string());
+            };
+            rates.push(ExchangeRate {
+                date,
+                from_currency: base.clone(),
+                to_currency: currency.parse().map_err(|e: &str| e.to_string())?,
+                rate: rate.parse().map_err(|e: &str| e.to_string())?,
+            });
+        }
+    }
+
+    if rates.is_empty() {
+        return Err("No rates found in XML document".to_string());
+    }
+
+    Ok(rates)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+/// Extracts `name="value"` (or single quoted) from the inside of an XML tag.
+fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(pos) = rest.find(name) {
+        let preceded_by_space = rest[..pos].ends_with(char::is_whitespace);
+        let after = rest[pos + name.len()..].trim_start();
+        if preceded_by_space && let Some(after) = after.strip_prefix('=') {
+            let after = after.trim_start();
+            let quote = after.chars().next()?;
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+            let value = &after[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        rest = &rest[pos + name.len()..];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_long_csv() {
+        let input = "date,from,to,rate\n2024-01-02,usd,CAD,1.3312\n\n2024-01-03,EUR,CAD,1.4601\n";
+        let rates = parse_rates_csv(input).unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].date, date("2024-01-02"));
+        assert_eq!(rates[0].from_currency.as_str(), "USD");
+        assert_eq!(rates[0].rate.to_string(), "1.3312");
+    }
+
+    #[test]
+    fn test_wide_ecb_csv() {
+        let input = "Date,USD,JPY,CYP,\n2024-01-02,1.0956,155.39,N/A,\n";
+        let rates = parse_rates_csv(input).unwrap();
+        assert_eq!(rates.len(), 2);
+        assert!(rates.iter().all(|r| r.from_currency.as_str() == "EUR"));
+        assert_eq!(rates[1].to_currency.as_str(), "JPY");
+    }
+
+    #[test]
+    fn test_csv_errors_report_line() {
+        let input = "date,from,to,rate\n2024-01-02,USD,CAD,abc\n";
+        let err = parse_rates_csv(input).err().unwrap();
+        assert!(err.starts_with("Line 2"));
+        assert!(parse_rates_csv("when,what\n").is_err());
+        assert!(parse_rates_csv("date,from,to,rate\n2024-01-02,USD,CAD\n").is_err());
+    }
+
+    #[test]
+    fn test_ecb_xml() {
+        let input = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+    <Cube>
+        <Cube time='2024-01-03'>
+            <Cube currency='USD' rate='1.0919'/>
+            <Cube currency='CAD' rate='1.4601'/>
+        </Cube>
+        <Cube time="2024-01-02">
+            <Cube currency="USD" rate="1.0956"/>
+        </Cube>
+    </Cube>
+</gesmes:Envelope>"#;
+        let rates = parse_ecb_xml(input).unwrap();
+        assert_eq!(rates.len(), 3);
+        assert_eq!(rates[1].to_currency.as_str(), "CAD");
+        assert_eq!(rates[1].date, date("2024-01-03"));
+        assert_eq!(rates[2].date, date("2024-01-02"));
+        assert_eq!(rates[2].rate.to_string(), "1.0956");
+    }
+
+    #[test]
+    fn test_ecb_xml_requires_rates() {
+        assert!(parse_ecb_xml("<root></root>").is_err());
+        assert!(parse_ecb_xml("<Cube currency='USD' rate='1.1'/>").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{env, net::SocketAddr};
+use std::{env, net::SocketAddr, sync::Arc};
 
 use axum::{Extension, Router};
 use sqlx::migrate::Migrator;
@@ -7,14 +7,26 @@ use tower_http::compression::{CompressionLayer, CompressionLevel, predicate::{Pr
 use dotenv::dotenv;
 
 mod routes;
+mod error;
 mod db;
 mod models;
 mod passwords;
 mod time_conversion;
 mod middleware;
+mod money;
+mod currency;
+mod exchange;
+mod sessions;
+mod tokens;
+mod alerts;
+mod recurring;
+mod storage;
+mod csv;
+mod encoding;
 
-use routes::{me, transactions, signup, login, logout, categories, import};
+use routes::{me, transactions, signup, login, logout, categories, import, api_tokens, exchange_rates, accounts, transfers, reports, budgets, spending_limits, notifications, recurring_transactions, search, tags, attachments, csv_import};
 use db::init_db_pool;
+use storage::{LocalStore, Storage};
 
 static MIGRATOR: Migrator = sqlx::migrate!();
 
@@ -32,18 +44,36 @@ async fn main() {
     let db = init_db_pool().await;
     MIGRATOR.run(&db).await.expect("Failed to run migrations");
 
+    tokio::spawn(recurring::run_scheduler(db.clone()));
+
+    let storage: Storage = Arc::new(LocalStore::from_env());
+
     let api_routes = Router::new()
         .merge(me::routes())
         .merge(transactions::routes())
+        .merge(search::routes())
         .merge(categories::routes())
+        .merge(accounts::routes())
+        .merge(transfers::routes())
+        .merge(reports::routes())
+        .merge(budgets::routes())
+        .merge(spending_limits::routes())
+        .merge(notifications::routes())
+        .merge(recurring_transactions::routes())
+        .merge(tags::routes())
+        .merge(attachments::routes())
         .merge(signup::routes())
         .merge(login::routes())
         .merge(logout::routes())
-        .merge(import::routes());
+        .merge(import::routes())
+        .merge(csv_import::routes())
+        .merge(api_tokens::routes())
+        .merge(exchange_rates::routes());
 
     let app = Router::new()
         .merge(api_routes)
         .layer(Extension(db))
+        .layer(Extension(storage))
         .layer(CookieManagerLayer::new())
         .layer(
             CompressionLayer::new()
@@ -56,5 +86,5 @@ async fn main() {
     println!("✅ Backend Listening on http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
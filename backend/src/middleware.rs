@@ -1,58 +1,208 @@
-use axum::{extract::FromRequestParts, http::{StatusCode, request::Parts}, Extension};
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{extract::{ConnectInfo, FromRequestParts}, http::{header::{AUTHORIZATION, USER_AGENT}, request::Parts}, Extension};
 use axum_extra::extract::cookie::CookieJar;
 use sqlx::PgPool;
-use uuid::Uuid;
 
-use crate::{models::user::User, time_conversion::convert_time_to_chrono};
+use crate::{
+    error::AppError,
+    models::{api_token::TokenScope, user::User},
+    passwords::verify_password,
+    sessions::{SESSION_COOKIE, SESSION_TTL_DAYS},
+    time_conversion::convert_time_to_chrono,
+    tokens::{hash_token, parse_api_token}
+};
 
 pub struct AuthSession(pub User);
 
+/// How the current request was authenticated. Inserted into the request extensions by `AuthSession`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    Session,
+    ApiToken(TokenScope),
+}
+
+impl AuthMethod {
+    /// Managing API tokens and sessions takes an interactive login, so a leaked token can't mint
+    /// more tokens or lock the owner out of their own account.
+    pub fn require_session(self, message: impl Into<String>) -> Result<(), AppError> {
+        match self {
+            AuthMethod::Session => Ok(()),
+            AuthMethod::ApiToken(_) => Err(AppError::forbidden(message)),
+        }
+    }
+}
+
 impl<S> FromRequestParts<S> for AuthSession
 where
     PgPool: Send + Sync,
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, &'static str);
+    type Rejection = AppError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let Extension(pool) = Extension::<PgPool>::from_request_parts(parts, state)
             .await
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "State unavailable"))?;
+            .map_err(|_| AppError::internal())?;
+
+        // API tokens take precedence over the session cookie
+        let bearer_token = parts.headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+
+        if let Some(token) = bearer_token {
+            let (user, scope) = authenticate_api_token(&pool, &token).await?;
+
+            if scope == TokenScope::Read && !parts.method.is_safe() {
+                return Err(AppError::forbidden("Token is read-only"));
+            }
+
+            parts.extensions.insert(AuthMethod::ApiToken(scope));
+            return Ok(AuthSession(user));
+        }
 
         let jar = CookieJar::from_request_parts(parts, state)
             .await
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to extract cookies"))?;
+            .map_err(|_| AppError::internal())?;
 
-        let Some(session_cookie) = jar.get("session") else {
-            return Err((StatusCode::UNAUTHORIZED, "No session cookie"));
+        let Some(session_cookie) = jar.get(SESSION_COOKIE) else {
+            return Err(AppError::unauthorized("No session cookie"));
         };
 
-        let user_id = session_cookie
-            .value()
-            .parse::<Uuid>()
-            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid session token"))?;
+        let user = authenticate_session(&pool, session_cookie.value()).await?;
+
+        parts.extensions.insert(AuthMethod::Session);
+        Ok(AuthSession(user))
+    }
+}
 
-        let user_record = sqlx::query!(
-            "SELECT id, username, password_hash, created_at FROM users WHERE id = $1",
-            user_id
+async fn authenticate_session(pool: &PgPool, token: &str) -> Result<User, AppError> {
+    // Look up the session and slide its expiry forward in a single round trip
+    let user_record = sqlx::query!(
+        r#"
+        WITH active_session AS (
+            UPDATE sessions
+            SET last_seen = now(), expires_at = now() + make_interval(days => $2)
+            WHERE token_hash = $1 AND expires_at > now()
+            RETURNING user_id
         )
-        .fetch_optional(&pool)
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load user"))?;
-
-        let user = match user_record {
-            Some(user) => Some(User {
-                id: user.id,
-                username: user.username,
-                password_hash: user.password_hash,
-                created_at: convert_time_to_chrono(user.created_at),
-            }),
-            None => None,
-        };
+        SELECT u.id, u.username, u.password_hash, u.base_currency, u.created_at
+        FROM active_session s
+        JOIN users u ON u.id = s.user_id
+        "#,
+        hash_token(token),
+        SESSION_TTL_DAYS
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match user_record {
+        Some(user) => Ok(User {
+            id: user.id,
+            username: user.username,
+            password_hash: user.password_hash,
+            base_currency: user.base_currency.into(),
+            created_at: convert_time_to_chrono(user.created_at),
+        }),
+        None => Err(AppError::unauthorized("Invalid session")),
+    }
+}
+
+async fn authenticate_api_token(pool: &PgPool, token: &str) -> Result<(User, TokenScope), AppError> {
+    let Some((token_id, secret)) = parse_api_token(token) else {
+        return Err(AppError::unauthorized("Invalid API token"));
+    };
+
+    let record = sqlx::query!(
+        r#"
+        SELECT t.secret_hash, t.scope, u.id, u.username, u.password_hash, u.base_currency, u.created_at
+        FROM api_tokens t
+        JOIN users u ON u.id = t.user_id
+        WHERE t.id = $1 AND (t.expires_at IS NULL OR t.expires_at > now())
+        "#,
+        token_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(record) = record else {
+        return Err(AppError::unauthorized("Invalid API token"));
+    };
+
+    if !matches!(verify_password(&record.secret_hash, secret), Ok(true)) {
+        return Err(AppError::unauthorized("Invalid API token"));
+    }
+
+    sqlx::query!(
+        r#"UPDATE api_tokens SET last_used_at = now() WHERE id = $1"#,
+        token_id
+    )
+    .execute(pool)
+    .await?;
+
+    let user = User {
+        id: record.id,
+        username: record.username,
+        password_hash: record.password_hash,
+        base_currency: record.base_currency.into(),
+        created_at: convert_time_to_chrono(record.created_at),
+    };
+
+    Ok((user, TokenScope::from_db(&record.scope)))
+}
+
+/// Describes the client making the request, recorded alongside sessions so users can tell their devices apart.
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts.headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        // Prefer the address reported by the reverse proxy, falling back to the socket peer.
+        // nginx overwrites X-Real-IP with the peer it saw, unlike X-Forwarded-For whose first
+        // entries come from the client and can be anything.
+        let real_ip = parts.headers
+            .get("x-real-ip")
+            .and_then(|value| value.to_str().ok())
+            .map(|ip| ip.trim().to_string())
+            .filter(|ip| !ip.is_empty());
+
+        let ip_address = real_ip.or_else(|| {
+            parts.extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        });
+
+        Ok(ClientInfo { user_agent, ip_address })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    use super::*;
+
+    #[test]
+    fn test_only_sessions_manage_credentials() {
+        assert!(AuthMethod::Session.require_session("Not allowed").is_ok());
 
-        match user {
-            Some(user) => Ok(AuthSession(user)),
-            None => Err((StatusCode::UNAUTHORIZED, "Invalid session")),
+        for scope in [TokenScope::Read, TokenScope::ReadWrite] {
+            let error = AuthMethod::ApiToken(scope).require_session("Not allowed").unwrap_err();
+            assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
         }
     }
 }
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::Currency, models::transaction::Transaction, money::Money};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    Checking,
+    Savings,
+    CreditCard,
+    Cash,
+    Investment,
+    Other,
+}
+
+impl AccountType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountType::Checking => "checking",
+            AccountType::Savings => "savings",
+            AccountType::CreditCard => "credit_card",
+            AccountType::Cash => "cash",
+            AccountType::Investment => "investment",
+            AccountType::Other => "other",
+        }
+    }
+
+    pub fn from_db(value: &str) -> AccountType {
+        match value {
+            "checking" => AccountType::Checking,
+            "savings" => AccountType::Savings,
+            "credit_card" => AccountType::CreditCard,
+            "cash" => AccountType::Cash,
+            "investment" => AccountType::Investment,
+            _ => AccountType::Other,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Account {
+    pub id: i32,
+    pub name: String,
+    pub account_type: AccountType,
+    pub currency: Currency,
+    pub opening_balance: Money,
+    /// Closed accounts keep their history but take no new transactions, transfers or imports
+    pub closed: bool,
+    /// Opening balance plus every transaction recorded against the account
+    pub balance: Money,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NewAccount {
+    pub name: String,
+    pub account_type: AccountType,
+    /// Defaults to the user's base currency
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    pub opening_balance: Money,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+/// Changes to an account. Fields left out keep their current value.
+#[derive(Deserialize)]
+pub struct UpdateAccount {
+    pub name: Option<String>,
+    pub account_type: Option<AccountType>,
+    pub currency: Option<Currency>,
+    pub opening_balance: Option<Money>,
+    pub closed: Option<bool>,
+}
+
+/// A transaction as it appears in an account's ledger.
+#[derive(Serialize)]
+pub struct LedgerEntry {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    /// Account balance right after this transaction
+    pub running_balance: Money,
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::Currency, money::Money};
+
+#[derive(Serialize)]
+pub struct Budget {
+    pub id: i32,
+    pub category_id: i32,
+    /// `YYYY-MM`
+    pub month: String,
+    /// Planned spending in the user's base currency
+    pub amount: Money,
+    pub rollover: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NewBudget {
+    pub category_id: i32,
+    /// `YYYY-MM`
+    pub month: String,
+    pub amount: Money,
+    #[serde(default)]
+    pub rollover: bool,
+}
+
+#[derive(Deserialize)]
+pub struct BudgetUpdate {
+    pub amount: Money,
+    #[serde(default)]
+    pub rollover: bool,
+}
+
+#[derive(Deserialize)]
+pub struct BudgetFilter {
+    /// `YYYY-MM`
+    pub month: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BudgetReport {
+    /// `YYYY-MM`
+    pub month: String,
+    pub currency: Currency,
+    pub budgets: Vec<BudgetReportLine>,
+}
+
+/// How a category (including all of its subcategories) is doing against its budget.
+#[derive(Serialize)]
+pub struct BudgetReportLine {
+    pub budget_id: i32,
+    pub category_id: i32,
+    pub category_name: String,
+    pub rollover: bool,
+    pub budgeted: Money,
+    /// Remaining amount brought forward from last month, negative when it was overspent
+    pub carried_over: Money,
+    pub spent: Money,
+    /// `budgeted + carried_over - spent`
+    pub remaining: Money,
+    /// Expenses this month left out of `spent` because no exchange rate was available
+    pub unconverted_count: i64,
+}
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 
+use crate::{models::transaction::Transaction, money::Money};
+
 #[derive(Serialize, sqlx::FromRow)]
 pub struct Category {
     pub id: i32,
@@ -9,8 +11,88 @@ pub struct Category {
     pub created_at: DateTime<Utc>,
 }
 
+impl Category {
+    /// Builds a category from LEFT JOINed columns, which are all present or all missing.
+    pub fn from_columns(
+        id: Option<i32>,
+        name: Option<String>,
+        parent_id: Option<i32>,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Option<Category> {
+        Some(Category {
+            id: id?,
+            name: name?,
+            parent_id,
+            created_at: created_at?,
+        })
+    }
+}
+
+/// A transaction listed under a category. A split transaction is listed whole, with
+/// `category_amount` holding only the splits that fall under the category.
+#[derive(Serialize)]
+pub struct CategoryTransaction {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    /// Signed like `amount` and in the same currency
+    pub category_amount: Money,
+}
+
 #[derive(Deserialize)]
 pub struct NewCategory {
     pub name: String,
     pub parent_id: Option<i32>,
 }
+
+/// A category with its place in the hierarchy and the transactions filed under it.
+/// Totals are signed (spending is negative) and in the user's base currency.
+#[derive(Serialize)]
+pub struct CategoryTreeNode {
+    pub id: i32,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    /// 0 for top-level categories
+    pub depth: i32,
+    /// Names from the root down, e.g. `Food / Groceries`
+    pub path: String,
+    /// Transactions filed directly under this category
+    pub own_count: i64,
+    pub own_total: Money,
+    /// Transactions in this category and all of its subcategories
+    pub total_count: i64,
+    pub total: Money,
+    /// Transactions left out of `total` because no exchange rate was available
+    pub unconverted_count: i64,
+    pub children: Vec<CategoryTreeNode>,
+}
+
+#[derive(Deserialize)]
+pub struct CategoryMerge {
+    /// The category that takes over everything filed under the merged one
+    pub target_id: i32,
+}
+
+/// How much was moved over to the target category.
+#[derive(Serialize)]
+pub struct CategoryMergeResult {
+    pub target_id: i32,
+    pub transactions: u64,
+    pub children: u64,
+    pub budgets: u64,
+    pub recurring_transactions: u64,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteStrategy {
+    /// Move everything in the category to `target`, as if merging it
+    Reassign,
+    /// Move the category's children up to its own parent
+    PromoteChildren,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteCategoryQuery {
+    pub strategy: Option<DeleteStrategy>,
+    pub target: Option<i32>,
+}
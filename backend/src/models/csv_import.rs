@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{models::transaction::TransactionKind, money::Money};
+
+/// Which columns of a bank's CSV export hold what, and how to read them.
+/// Columns are zero-based positions, so files without a header row work too.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CsvMapping {
+    #[serde(default = "default_true")]
+    pub has_header: bool,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    pub date_column: usize,
+    /// A `chrono` format string, e.g. `%Y-%m-%d` or `%d/%m/%Y`
+    pub date_format: String,
+    pub description_column: usize,
+    /// A signed amount. Leave empty when the bank uses separate debit and credit columns.
+    #[serde(default)]
+    pub amount_column: Option<usize>,
+    /// Money going out, usually written as a positive number
+    #[serde(default)]
+    pub debit_column: Option<usize>,
+    /// Money coming in
+    #[serde(default)]
+    pub credit_column: Option<usize>,
+    /// `.` or `,`. The other one is treated as a thousands separator.
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    /// Flips every amount, for exports that show purchases as positive numbers
+    #[serde(default)]
+    pub negate: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+#[derive(Serialize)]
+pub struct CsvProfile {
+    pub id: i32,
+    pub name: String,
+    #[serde(flatten)]
+    pub mapping: CsvMapping,
+    /// Imported transactions are filed under this category
+    pub default_category_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NewCsvProfile {
+    pub name: String,
+    #[serde(flatten)]
+    pub mapping: CsvMapping,
+    #[serde(default)]
+    pub default_category_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct CsvImportQuery {
+    pub profile_id: i32,
+    /// The account the statement belongs to. Its currency is used for every row.
+    #[serde(default)]
+    pub account_id: Option<i32>,
+    /// Parse and report on the file without saving anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A row of the file as it would be imported.
+#[derive(Serialize)]
+pub struct CsvRow {
+    /// Line in the file the row starts on, counting from 1
+    pub line: usize,
+    pub created_at: DateTime<Utc>,
+    pub description: String,
+    pub amount: Money,
+    pub kind: TransactionKind,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct CsvRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct CsvImportPreview {
+    /// The header row, empty when the profile says the file has none
+    pub columns: Vec<String>,
+    pub rows: Vec<CsvRow>,
+    pub errors: Vec<CsvRowError>,
+}
+
+#[derive(Serialize)]
+pub struct CsvImportResult {
+    pub imported: usize,
+}
@@ -0,0 +1,40 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::Currency, money::Rate};
+
+/// One unit of `from_currency` is worth `rate` units of `to_currency` on `date`.
+#[derive(Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub date: NaiveDate,
+    pub from_currency: Currency,
+    pub to_currency: Currency,
+    pub rate: Rate,
+}
+
+#[derive(Deserialize)]
+pub struct ExchangeRateFilter {
+    pub from: Option<Currency>,
+    pub to: Option<Currency>,
+}
+
+#[derive(Deserialize)]
+pub struct ExchangeRateQuery {
+    pub from: Currency,
+    pub to: Currency,
+    pub date: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct ExchangeRateLookup {
+    pub date: NaiveDate,
+    pub from_currency: Currency,
+    pub to_currency: Currency,
+    /// `None` when no rate on or before `date` is known
+    pub rate: Option<Rate>,
+}
+
+#[derive(Serialize)]
+pub struct ExchangeRateImportResult {
+    pub upserted: usize,
+}
@@ -1,5 +1,7 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::Currency, models::transaction::TransactionKind, money::Money};
 
 #[derive(Deserialize)]
 pub struct ImportCategory {
@@ -13,8 +15,14 @@ pub struct ImportCategory {
 pub struct ImportTransaction {
     pub category_name: String,
     pub description: String,
-    pub amount: f64,
+    pub amount: Money,
+    #[serde(default)]
+    pub kind: Option<TransactionKind>,
+    #[serde(default)]
+    pub currency: Option<Currency>,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -22,3 +30,18 @@ pub struct ImportPayload {
     pub categories: Vec<ImportCategory>,
     pub transactions: Vec<ImportTransaction>,
 }
+
+#[derive(Deserialize)]
+pub struct OfxImportQuery {
+    /// Every imported transaction is filed under this category
+    pub category_id: i32,
+    #[serde(default)]
+    pub account_id: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct OfxImportResult {
+    pub imported: u64,
+    /// Transactions already imported from an earlier statement
+    pub skipped: u64,
+}
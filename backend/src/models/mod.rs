@@ -2,3 +2,15 @@ pub mod transaction;
 pub mod user;
 pub mod category;
 pub mod import_payload;
+pub mod session;
+pub mod api_token;
+pub mod exchange_rate;
+pub mod account;
+pub mod transfer;
+pub mod report;
+pub mod budget;
+pub mod notification;
+pub mod recurring_transaction;
+pub mod tag;
+pub mod attachment;
+pub mod csv_import;
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::money::Money;
+
+#[derive(Serialize)]
+pub struct SpendingLimit {
+    pub category_id: i32,
+    /// Monthly limit in the user's base currency
+    pub amount: Money,
+    /// Percentages of the limit that raise a notification
+    pub thresholds: Vec<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NewSpendingLimit {
+    pub amount: Money,
+    pub thresholds: Option<Vec<i32>>,
+}
+
+#[derive(Serialize)]
+pub struct Notification {
+    pub id: i32,
+    pub category_id: i32,
+    /// `YYYY-MM`
+    pub month: String,
+    pub threshold: i32,
+    pub spent: Money,
+    pub limit: Money,
+    pub message: String,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NotificationFilter {
+    #[serde(default)]
+    pub unread: bool,
+}
+
+#[derive(Deserialize)]
+pub struct NotificationUpdate {
+    pub read: bool,
+}
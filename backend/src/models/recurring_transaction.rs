@@ -0,0 +1,94 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::Currency, models::transaction::{NewTransaction, TransactionKind}, money::Money};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "daily",
+            Frequency::Weekly => "weekly",
+            Frequency::Monthly => "monthly",
+            Frequency::Yearly => "yearly",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Frequency {
+        match value {
+            "daily" => Frequency::Daily,
+            "weekly" => Frequency::Weekly,
+            "yearly" => Frequency::Yearly,
+            _ => Frequency::Monthly,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RecurringTransaction {
+    pub id: i32,
+    pub category_id: i32,
+    pub account_id: Option<i32>,
+    pub kind: TransactionKind,
+    pub description: String,
+    pub amount: Money,
+    pub currency: Currency,
+    pub frequency: Frequency,
+    /// Every `interval` days/weeks/months/years
+    pub interval: i32,
+    pub start_date: NaiveDate,
+    /// Last possible occurrence, or `None` to repeat forever
+    pub end_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NewRecurringTransaction {
+    pub category_id: i32,
+    pub account_id: Option<i32>,
+    /// Inferred from the sign of `amount` when omitted
+    pub kind: Option<TransactionKind>,
+    pub description: String,
+    pub amount: Money,
+    /// Defaults to the account's currency, or the user's base currency
+    pub currency: Option<Currency>,
+    pub frequency: Frequency,
+    #[serde(default = "default_interval")]
+    pub interval: i32,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+fn default_interval() -> i32 {
+    1
+}
+
+impl NewRecurringTransaction {
+    /// The transaction each occurrence creates, used to validate the template like any other transaction.
+    pub fn template(&self) -> NewTransaction {
+        NewTransaction {
+            category_id: Some(self.category_id),
+            account_id: Some(self.account_id),
+            kind: self.kind,
+            description: self.description.clone(),
+            amount: self.amount.clone(),
+            currency: self.currency.clone(),
+            created_at: None,
+            tags: None,
+            splits: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    pub count: Option<usize>,
+}
@@ -0,0 +1,110 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::Currency, money::Money};
+
+/// Inclusive date range shared by the reports. Dates are interpreted in UTC.
+#[derive(Deserialize)]
+pub struct ReportRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct CashFlowReport {
+    /// Every amount in the report is converted to this currency
+    pub currency: Currency,
+    pub months: Vec<CashFlowMonth>,
+}
+
+#[derive(Serialize)]
+pub struct CashFlowMonth {
+    /// `YYYY-MM`
+    pub month: String,
+    pub income: Money,
+    /// Total spending, as a positive amount
+    pub expenses: Money,
+    /// `income - expenses`
+    pub net: Money,
+    /// Transactions left out of the totals because no exchange rate was available
+    pub unconverted_count: i64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    Week,
+    #[default]
+    Month,
+    Year,
+}
+
+impl Granularity {
+    /// The matching Postgres `date_trunc` field.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+            Granularity::Year => "year",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SummaryQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub granularity: Granularity,
+}
+
+#[derive(Serialize)]
+pub struct SummaryReport {
+    /// Every amount in the report is converted to this currency
+    pub currency: Currency,
+    pub granularity: Granularity,
+    pub periods: Vec<SummaryPeriod>,
+}
+
+#[derive(Serialize)]
+pub struct SummaryPeriod {
+    /// First day of the period; weeks start on Monday
+    pub start: NaiveDate,
+    pub categories: Vec<CategorySummary>,
+}
+
+/// Signed totals for one category in one period: spending is negative, income positive.
+#[derive(Serialize)]
+pub struct CategorySummary {
+    pub category_id: i32,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    /// Transactions filed directly under this category
+    pub own_total: Money,
+    /// Transactions in this category and all of its subcategories
+    pub total: Money,
+    /// Transactions left out of `total` because no exchange rate was available
+    pub unconverted_count: i64,
+}
+
+#[derive(Serialize)]
+pub struct TagReport {
+    /// Every amount in the report is converted to this currency
+    pub currency: Currency,
+    pub tags: Vec<TagSummary>,
+}
+
+/// Income and spending carrying one tag. A transaction with several tags counts towards each of them.
+#[derive(Serialize)]
+pub struct TagSummary {
+    pub tag_id: i32,
+    pub name: String,
+    pub transaction_count: i64,
+    pub income: Money,
+    /// Total spending, as a positive amount
+    pub expenses: Money,
+    /// `income - expenses`
+    pub net: Money,
+    /// Transactions left out of the totals because no exchange rate was available
+    pub unconverted_count: i64,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    /// How many transactions carry the tag
+    pub transaction_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NewTag {
+    pub name: String,
+}
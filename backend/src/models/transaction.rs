@@ -1,21 +1,188 @@
-use chrono::{DateTime, Utc};
-use serde::{Serialize, Deserialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::models::category::Category;
+use crate::{currency::Currency, models::{category::Category, transfer::TransferLink}, money::Money};
+
+/// Amounts are signed: expenses are negative (or zero), income is positive (or zero).
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Expense,
+    Income,
+    /// A leg of a transfer between accounts; neither spending nor income
+    Transfer,
+}
+
+impl TransactionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionKind::Expense => "expense",
+            TransactionKind::Income => "income",
+            TransactionKind::Transfer => "transfer",
+        }
+    }
+
+    pub fn from_db(value: &str) -> TransactionKind {
+        match value {
+            "income" => TransactionKind::Income,
+            "transfer" => TransactionKind::Transfer,
+            _ => TransactionKind::Expense,
+        }
+    }
+
+    /// The kind implied by an amount's sign when the client doesn't say.
+    pub fn from_amount(amount: &Money) -> TransactionKind {
+        if amount.is_negative() {
+            TransactionKind::Expense
+        } else {
+            TransactionKind::Income
+        }
+    }
+
+    /// Whether `amount` has the sign this kind requires.
+    pub fn accepts(&self, amount: &Money) -> bool {
+        match self {
+            TransactionKind::Expense => !amount.is_positive(),
+            TransactionKind::Income => !amount.is_negative(),
+            TransactionKind::Transfer => true,
+        }
+    }
+}
 
 #[derive(Serialize, sqlx::FromRow)]
 pub struct Transaction {
     pub id: i32,
-    pub category: Category,
+    /// `None` for the legs of a transfer, which don't count as spending
+    pub category: Option<Category>,
+    pub account_id: Option<i32>,
+    pub transfer: Option<TransferLink>,
+    pub kind: TransactionKind,
     pub description: String,
-    pub amount: f64,
+    pub amount: Money,
+    pub currency: Currency,
+    /// `amount` expressed in the user's base currency, or `None` when it can't be converted
+    pub base_amount: Option<Money>,
+    /// Tag names in alphabetical order
+    pub tags: Vec<String>,
+    /// Empty unless the transaction is split across categories
+    pub splits: Vec<TransactionSplit>,
     pub created_at: DateTime<Utc>,
 }
 
+/// One part of a split transaction. Splits are signed like the transaction and add up to its amount.
+#[derive(Serialize, Deserialize)]
+pub struct TransactionSplit {
+    pub category_id: i32,
+    pub amount: Money,
+    pub memo: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct NewTransaction {
-    pub category_id: i32,
+    /// Required for income and expenses. Leave it out when editing a leg of a transfer
+    #[serde(default)]
+    pub category_id: Option<i32>,
+    /// On update, leaving it out keeps the account and `null` takes the transaction out of it
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub account_id: Option<Option<i32>>,
+    /// Inferred from the sign of `amount` when omitted
+    pub kind: Option<TransactionKind>,
     pub description: String,
-    pub amount: f64,
+    pub amount: Money,
+    /// Defaults to the account's currency, or the user's base currency
+    pub currency: Option<Currency>,
     pub created_at: Option<DateTime<Utc>>,
+    /// Tag names; unknown tags are created. Leaves the tags unchanged on update when omitted
+    pub tags: Option<Vec<String>>,
+    /// Splits the transaction across categories; an empty list removes the splits.
+    /// Leaves the splits unchanged on update when omitted
+    pub splits: Option<Vec<TransactionSplit>>,
+}
+
+/// Tells a field sent as `null` (`Some(None)`) apart from one left out (`None`).
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSort {
+    #[default]
+    CreatedAt,
+    Amount,
+    Description,
+}
+
+impl TransactionSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionSort::CreatedAt => "created_at",
+            TransactionSort::Amount => "amount",
+            TransactionSort::Description => "description",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Filters, sorting and paging for `GET /transactions`. Every filter is optional.
+#[derive(Deserialize)]
+pub struct TransactionQuery {
+    /// First day to include (UTC)
+    pub from: Option<NaiveDate>,
+    /// Last day to include (UTC)
+    pub to: Option<NaiveDate>,
+    pub category_id: Option<i32>,
+    /// Also match transactions in subcategories of `category_id`
+    #[serde(default)]
+    pub include_descendants: bool,
+    /// Signed, so expenses are matched with negative bounds
+    pub min_amount: Option<Money>,
+    pub max_amount: Option<Money>,
+    /// Case-insensitive substring of the description
+    pub description: Option<String>,
+    /// Only transactions carrying this tag
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub sort: TransactionSort,
+    #[serde(default)]
+    pub direction: SortDirection,
+    pub limit: Option<i64>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    /// Number of transactions matching the filters, across all pages
+    pub total: i64,
+    /// Pass as `cursor` to fetch the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    /// Search terms, in web search syntax (`"exact phrase"`, `or`, `-excluded`)
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    pub rank: f32,
+    /// HTML-escaped description with the matching words wrapped in `<mark>` tags
+    pub snippet: String,
 }
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::Currency, money::Money};
+
+/// Attached to each leg of a transfer, naming both sides of the move.
+#[derive(Serialize)]
+pub struct TransferLink {
+    pub transfer_id: i32,
+    pub from_account_id: i32,
+    pub from_account_name: String,
+    pub to_account_id: i32,
+    pub to_account_name: String,
+}
+
+impl TransferLink {
+    /// Builds the link from LEFT JOINed columns, which are all present or all missing.
+    pub fn from_columns(
+        transfer_id: Option<i32>,
+        from_account_id: Option<i32>,
+        from_account_name: Option<String>,
+        to_account_id: Option<i32>,
+        to_account_name: Option<String>,
+    ) -> Option<TransferLink> {
+        Some(TransferLink {
+            transfer_id: transfer_id?,
+            from_account_id: from_account_id?,
+            from_account_name: from_account_name?,
+            to_account_id: to_account_id?,
+            to_account_name: to_account_name?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Transfer {
+    pub id: i32,
+    pub description: String,
+    pub from_account_id: i32,
+    pub from_account_name: String,
+    /// Amount leaving the source account, in its currency
+    pub amount: Money,
+    pub from_currency: Currency,
+    pub from_transaction_id: i32,
+    pub to_account_id: i32,
+    pub to_account_name: String,
+    /// Amount arriving in the destination account, in its currency
+    pub to_amount: Money,
+    pub to_currency: Currency,
+    pub to_transaction_id: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NewTransfer {
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    pub description: String,
+    /// Positive amount leaving the source account
+    pub amount: Money,
+    /// Required when the accounts use different currencies, otherwise defaults to `amount`
+    pub to_amount: Option<Money>,
+    pub created_at: Option<DateTime<Utc>>,
+}
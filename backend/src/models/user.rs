@@ -2,11 +2,14 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::currency::Currency;
+
 #[derive(Serialize)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
     pub password_hash: String,
+    pub base_currency: Currency,
     pub created_at: DateTime<Utc>,
 }
 
@@ -15,3 +18,8 @@ pub struct NewUser {
     pub username: String,
     pub password: String,
 }
+
+#[derive(Deserialize)]
+pub struct UpdateUser {
+    pub base_currency: Option<Currency>,
+}
@@ -0,0 +1,243 @@
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use bigdecimal::BigDecimal;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Maximum number of fractional digits accepted for an amount. Matches the `NUMERIC(19, 4)` columns.
+pub const MONEY_SCALE: i64 = 4;
+
+/// Maximum number of integer digits accepted for an amount (19 - 4).
+const MONEY_INTEGER_DIGITS: i64 = 15;
+
+/// Exchange rates are stored as `NUMERIC(19, 10)`.
+const RATE_SCALE: i64 = 10;
+const RATE_INTEGER_DIGITS: i64 = 9;
+
+/// An exact decimal amount of money.
+///
+/// Serialized as a JSON string (e.g. `"-12.34"`) so no precision is lost in transit.
+/// Deserializes from either a string or a JSON number, and rejects anything that
+/// is not finite or carries more precision than the database can store.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Money(BigDecimal);
+
+impl Money {
+    pub fn as_decimal(&self) -> &BigDecimal {
+        &self.0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > BigDecimal::from(0)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < BigDecimal::from(0)
+    }
+
+    pub fn abs(&self) -> Money {
+        Money(self.0.abs())
+    }
+}
+
+impl From<BigDecimal> for Money {
+    fn from(value: BigDecimal) -> Self {
+        Money(value)
+    }
+}
+
+impl FromStr for Money {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_decimal(value, MONEY_SCALE, MONEY_INTEGER_DIGITS).map(Money)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.normalized().to_plain_string())
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DecimalVisitor(PhantomData))
+    }
+}
+
+/// An exact, strictly positive exchange rate. Same wire format as [`Money`], with more precision.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rate(BigDecimal);
+
+impl Rate {
+    pub fn as_decimal(&self) -> &BigDecimal {
+        &self.0
+    }
+}
+
+impl From<BigDecimal> for Rate {
+    fn from(value: BigDecimal) -> Self {
+        Rate(value)
+    }
+}
+
+impl FromStr for Rate {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let decimal = parse_decimal(value, RATE_SCALE, RATE_INTEGER_DIGITS)?;
+        if decimal <= BigDecimal::from(0) {
+            return Err("Exchange rate must be positive");
+        }
+        Ok(Rate(decimal))
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.normalized().to_plain_string())
+    }
+}
+
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DecimalVisitor(PhantomData))
+    }
+}
+
+/// Parses an exact decimal, rejecting non-finite values and anything that doesn't fit the column.
+fn parse_decimal(value: &str, max_scale: i64, max_integer_digits: i64) -> Result<BigDecimal, &'static str> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("Amount is empty");
+    }
+
+    // BigDecimal happily parses exponents, but NaN / inf are never valid
+    let lowered = value.to_ascii_lowercase();
+    if lowered.contains("nan") || lowered.contains("inf") {
+        return Err("Amount must be a finite number");
+    }
+
+    let decimal = BigDecimal::from_str(value).map_err(|_| "Amount is not a valid decimal number")?;
+    let decimal = decimal.normalized();
+
+    if decimal.fractional_digit_count() > max_scale {
+        return Err("Amount has too many decimal places");
+    }
+
+    if decimal.digits() as i64 - decimal.fractional_digit_count() > max_integer_digits {
+        return Err("Amount is too large");
+    }
+
+    Ok(decimal)
+}
+
+/// Accepts a decimal as either a JSON string or a JSON number.
+struct DecimalVisitor<T>(PhantomData<T>);
+
+impl<T: FromStr<Err = &'static str>> de::Visitor<'_> for DecimalVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal amount as a string or number")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<T, E> {
+        value.to_string().parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        value.to_string().parse().map_err(E::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<T, E> {
+        if !value.is_finite() {
+            return Err(E::custom("Amount must be a finite number"));
+        }
+        // Rust prints the shortest representation that round-trips, so 0.1 stays "0.1"
+        value.to_string().parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_json(json: &str) -> Result<Money, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn test_string_and_number_inputs_agree() {
+        assert_eq!(parse_json("\"0.1\"").unwrap(), parse_json("0.1").unwrap());
+        assert_eq!(parse_json("\"-42\"").unwrap(), parse_json("-42").unwrap());
+    }
+
+    #[test]
+    fn test_sums_are_exact() {
+        let a = parse_json("0.1").unwrap();
+        let b = parse_json("0.2").unwrap();
+        assert_eq!(Money::from(a.as_decimal() + b.as_decimal()).to_string(), "0.3");
+    }
+
+    #[test]
+    fn test_serializes_as_trimmed_string() {
+        let money = Money::from(BigDecimal::from_str("12.3400").unwrap());
+        assert_eq!(serde_json::to_string(&money).unwrap(), "\"12.34\"");
+        let money = Money::from(BigDecimal::from_str("100.0000").unwrap());
+        assert_eq!(serde_json::to_string(&money).unwrap(), "\"100\"");
+    }
+
+    #[test]
+    fn test_rejects_non_finite() {
+        assert!(parse_json("\"NaN\"").is_err());
+        assert!(parse_json("\"inf\"").is_err());
+        assert!(parse_json("\"-Infinity\"").is_err());
+    }
+
+    #[test]
+    fn test_rejects_over_precision() {
+        assert!(parse_json("\"1.00001\"").is_err());
+        assert!(parse_json("1.23456").is_err());
+        assert!(parse_json("\"1.2345\"").is_ok());
+        assert!(parse_json("\"1.50000\"").is_ok()); // trailing zeros carry no precision
+    }
+
+    #[test]
+    fn test_rejects_out_of_range() {
+        assert!(parse_json("\"1000000000000000\"").is_err());
+        assert!(parse_json("\"999999999999999.9999\"").is_ok());
+        assert!(parse_json("1e300").is_err());
+    }
+
+    #[test]
+    fn test_rate_bounds() {
+        assert!(serde_json::from_str::<Rate>("\"1.0956123456\"").is_ok());
+        assert!(serde_json::from_str::<Rate>("0").is_err());
+        assert!(serde_json::from_str::<Rate>("-1.2").is_err());
+        assert!(serde_json::from_str::<Rate>("\"1.00000000001\"").is_err());
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(parse_json("\"\"").is_err());
+        assert!(parse_json("\"12,34\"").is_err());
+        assert!(parse_json("true").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use chrono::{Days, Months, NaiveDate, Utc};
+use sqlx::{PgPool, Postgres};
+
+use crate::{
+    alerts::evaluate_spending_limits,
+    models::recurring_transaction::Frequency,
+    time_conversion::{convert_chrono_to_date, convert_date_to_chrono}
+};
+
+/// How often the background task looks for occurrences that have come due.
+const MATERIALIZE_EVERY: Duration = Duration::from_secs(60 * 60);
+
+/// When a recurring transaction happens.
+pub struct Schedule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
+}
+
+impl Schedule {
+    /// The `n`th occurrence counting from `start`, ignoring the end date.
+    /// Each occurrence is computed from the start date, so a schedule starting on the 31st
+    /// falls on the last day of shorter months without drifting earlier afterwards.
+    fn occurrence(&self, n: u32) -> Option<NaiveDate> {
+        let steps = n.checked_mul(self.interval)?;
+
+        match self.frequency {
+            Frequency::Daily => self.start.checked_add_days(Days::new(steps.into())),
+            Frequency::Weekly => self.start.checked_add_days(Days::new(u64::from(steps) * 7)),
+            Frequency::Monthly => self.start.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => self.start.checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+
+    /// Every occurrence strictly after `after` (or from the start when `None`), up to the end date.
+    pub fn occurrences_after(&self, after: Option<NaiveDate>) -> impl Iterator<Item = NaiveDate> + '_ {
+        (0..)
+            .map_while(|n| self.occurrence(n))
+            .take_while(|date| self.end.is_none_or(|end| *date <= end))
+            .skip_while(move |date| after.is_some_and(|after| *date <= after))
+    }
+}
+
+/// Turns every occurrence due on or before `today` into a transaction. Occurrences are only ever
+/// created once, even if the transaction they created is later deleted. Each template is handled in
+/// its own database transaction, so one that fails is logged and retried on the next run without
+/// holding up the others. Templates whose account is closed wait until it's reopened.
+/// Returns the number of transactions created.
+pub async fn materialize_due(pool: &PgPool, today: NaiveDate) -> Result<u64, sqlx::Error> {
+    let template_ids = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM recurring_transactions
+        WHERE start_date <= $1
+        AND (materialized_through IS NULL OR materialized_through < $1)
+        AND (account_id IS NULL OR account_id NOT IN (SELECT id FROM accounts WHERE closed))
+        "#,
+        convert_chrono_to_date(today)
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut created = 0;
+
+    for template_id in template_ids {
+        let result = async {
+            let mut tx = pool.begin().await?;
+            let count = materialize_template(&mut tx, template_id, today).await?;
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(count)
+        }
+        .await;
+
+        match result {
+            Ok(count) => created += count,
+            Err(e) => eprintln!("Failed to materialize recurring transaction {}: {}", template_id, e),
+        }
+    }
+
+    Ok(created)
+}
+
+/// Creates the transactions for one template's occurrences due on or before `today`, as part of `tx`.
+/// Returns the number of transactions created.
+pub async fn materialize_template(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    template_id: i32,
+    today: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    // Locked so the scheduler and an edit to the template can't both work through the same occurrences
+    let template = sqlx::query!(
+        r#"
+        SELECT id, user_id, category_id, account_id, kind, description, amount, currency,
+            frequency, interval, start_date, end_date, materialized_through
+        FROM recurring_transactions
+        WHERE id = $1
+        AND start_date <= $2
+        AND (materialized_through IS NULL OR materialized_through < $2)
+        AND (account_id IS NULL OR account_id NOT IN (SELECT id FROM accounts WHERE closed))
+        FOR UPDATE
+        "#,
+        template_id,
+        convert_chrono_to_date(today)
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(template) = template else {
+        return Ok(0);
+    };
+
+    let schedule = Schedule {
+        frequency: Frequency::from_db(&template.frequency),
+        interval: template.interval.max(1) as u32,
+        start: convert_date_to_chrono(template.start_date),
+        end: template.end_date.map(convert_date_to_chrono),
+    };
+
+    let due: Vec<_> = schedule
+        .occurrences_after(template.materialized_through.map(convert_date_to_chrono))
+        .take_while(|date| *date <= today)
+        .map(convert_chrono_to_date)
+        .collect();
+
+    // Occurrences are recorded at midnight UTC on the day they fall on
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO transactions (user_id, category_id, account_id, kind, description, amount, currency, created_at, recurring_id, occurrence_date)
+        SELECT $1, $2, $3, $4, $5, $6, $7, occurrence::timestamp AT TIME ZONE 'UTC', $8, occurrence
+        FROM UNNEST($9::date[]) AS occurrence
+        ON CONFLICT (recurring_id, occurrence_date) DO NOTHING
+        "#,
+        template.user_id,
+        template.category_id,
+        template.account_id,
+        template.kind,
+        template.description,
+        template.amount,
+        template.currency,
+        template.id,
+        &due
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    sqlx::query!(
+        r#"UPDATE recurring_transactions SET materialized_through = $1 WHERE id = $2"#,
+        convert_chrono_to_date(today),
+        template.id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    if inserted > 0 {
+        let occurred_at: Vec<_> = due.iter().map(|date| date.midnight().assume_utc()).collect();
+        evaluate_spending_limits(&mut **tx, template.user_id, &occurred_at).await?;
+    }
+
+    Ok(inserted)
+}
+
+/// Background task that keeps materializing recurring transactions as they come due.
+pub async fn run_scheduler(pool: PgPool) {
+    let mut ticker = tokio::time::interval(MATERIALIZE_EVERY);
+
+    loop {
+        ticker.tick().await;
+
+        if let Err(e) = materialize_due(&pool, Utc::now().date_naive()).await {
+            eprintln!("Failed to materialize recurring transactions: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn schedule(frequency: Frequency, interval: u32, start: NaiveDate, end: Option<NaiveDate>) -> Schedule {
+        Schedule { frequency, interval, start, end }
+    }
+
+    #[test]
+    fn test_weekly_with_interval() {
+        let s = schedule(Frequency::Weekly, 2, date(2024, 1, 1), None);
+        let dates: Vec<_> = s.occurrences_after(None).take(3).collect();
+
+        assert_eq!(dates, vec![date(2024, 1, 1), date(2024, 1, 15), date(2024, 1, 29)]);
+    }
+
+    #[test]
+    fn test_monthly_clamps_to_month_end_without_drifting() {
+        let s = schedule(Frequency::Monthly, 1, date(2024, 1, 31), None);
+        let dates: Vec<_> = s.occurrences_after(None).take(4).collect();
+
+        assert_eq!(dates, vec![date(2024, 1, 31), date(2024, 2, 29), date(2024, 3, 31), date(2024, 4, 30)]);
+    }
+
+    #[test]
+    fn test_yearly_on_leap_day() {
+        let s = schedule(Frequency::Yearly, 1, date(2024, 2, 29), None);
+        let dates: Vec<_> = s.occurrences_after(None).take(2).collect();
+
+        assert_eq!(dates, vec![date(2024, 2, 29), date(2025, 2, 28)]);
+    }
+
+    #[test]
+    fn test_stops_at_end_date() {
+        let s = schedule(Frequency::Daily, 1, date(2024, 1, 1), Some(date(2024, 1, 3)));
+        let dates: Vec<_> = s.occurrences_after(None).collect();
+
+        assert_eq!(dates, vec![date(2024, 1, 1), date(2024, 1, 2), date(2024, 1, 3)]);
+    }
+
+    #[test]
+    fn test_skips_already_materialized_occurrences() {
+        let s = schedule(Frequency::Monthly, 1, date(2024, 1, 15), None);
+        let dates: Vec<_> = s.occurrences_after(Some(date(2024, 3, 15))).take(2).collect();
+
+        assert_eq!(dates, vec![date(2024, 4, 15), date(2024, 5, 15)]);
+    }
+}
@@ -0,0 +1,288 @@
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    middleware::AuthSession,
+    models::{account::{Account, AccountType, LedgerEntry, NewAccount, UpdateAccount}, category::Category, transaction::{Transaction, TransactionKind}, transfer::TransferLink, user::User},
+    routes::transactions::attach_splits,
+    time_conversion::convert_time_to_chrono
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/accounts", get(list_accounts).post(create_account))
+        .route("/accounts/{id}", get(get_account).put(update_account).delete(delete_account))
+        .route("/accounts/{id}/transactions", get(get_ledger))
+}
+
+async fn list_accounts(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let rows: Vec<Account> = sqlx::query!(
+        r#"
+        SELECT a.id, a.name, a.account_type, a.currency, a.opening_balance, a.closed, a.created_at,
+            a.opening_balance + COALESCE(
+                (SELECT SUM(t.amount) FROM transactions t WHERE t.account_id = a.id), 0
+            ) AS "balance!"
+        FROM accounts a
+        WHERE a.user_id = $1
+        ORDER BY a.closed ASC, a.name ASC
+        "#,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| Account {
+        id: row.id,
+        name: row.name,
+        account_type: AccountType::from_db(&row.account_type),
+        currency: row.currency.into(),
+        opening_balance: row.opening_balance.into(),
+        closed: row.closed,
+        balance: row.balance.into(),
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+/// Loads a single account with its current balance.
+async fn fetch_account(pool: &PgPool, user: &User, id: i32) -> Result<Option<Account>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT a.id, a.name, a.account_type, a.currency, a.opening_balance, a.closed, a.created_at,
+            a.opening_balance + COALESCE(
+                (SELECT SUM(t.amount) FROM transactions t WHERE t.account_id = a.id), 0
+            ) AS "balance!"
+        FROM accounts a
+        WHERE a.id = $1 AND a.user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some(Account {
+        id: row.id,
+        name: row.name,
+        account_type: AccountType::from_db(&row.account_type),
+        currency: row.currency.into(),
+        opening_balance: row.opening_balance.into(),
+        closed: row.closed,
+        balance: row.balance.into(),
+        created_at: convert_time_to_chrono(row.created_at),
+    }))
+}
+
+async fn create_account(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewAccount>,
+) -> Result<impl IntoResponse, AppError> {
+    let currency = payload.currency.unwrap_or_else(|| user.base_currency.clone());
+
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO accounts (user_id, name, account_type, currency, opening_balance, closed)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, name) DO NOTHING
+        RETURNING id
+        "#,
+        user.id,
+        payload.name,
+        payload.account_type.as_str(),
+        currency.as_str(),
+        payload.opening_balance.as_decimal(),
+        payload.closed
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    let Some(record) = record else {
+        return Err(AppError::conflict("An account with this name already exists")
+            .with_field("name", "An account with this name already exists"));
+    };
+
+    let account = fetch_account(&pool, &user, record.id)
+        .await?
+        .ok_or_else(AppError::internal)?;
+
+    Ok((StatusCode::CREATED, Json(account)))
+}
+
+async fn get_account(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession
+) -> Result<impl IntoResponse, AppError> {
+    match fetch_account(&pool, &user, id).await? {
+        Some(account) => Ok(Json(account)),
+        None => Err(AppError::not_found("Account not found")),
+    }
+}
+
+async fn update_account(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<UpdateAccount>,
+) -> Result<impl IntoResponse, AppError> {
+    let existing = fetch_account(&pool, &user, id).await?;
+    let Some(existing) = existing else {
+        return Err(AppError::not_found("Account not found"));
+    };
+
+    // Changing the currency would silently reinterpret every amount already in the account
+    let currency = payload.currency.unwrap_or(existing.currency.clone());
+    if currency != existing.currency {
+        let has_transactions = sqlx::query!(
+            r#"SELECT 1 AS exists FROM transactions WHERE account_id = $1 LIMIT 1"#,
+            id
+        )
+        .fetch_optional(&pool)
+        .await?;
+
+        if has_transactions.is_some() {
+            return Err(AppError::conflict("The account already has transactions")
+                .with_field("currency", "Can't change the currency of an account with transactions"));
+        }
+    }
+
+    let opening_balance = payload.opening_balance.unwrap_or(existing.opening_balance);
+
+    sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET name = $1, account_type = $2, currency = $3, opening_balance = $4, closed = $5
+        WHERE id = $6 AND user_id = $7
+        "#,
+        payload.name.unwrap_or(existing.name),
+        payload.account_type.unwrap_or(existing.account_type).as_str(),
+        currency.as_str(),
+        opening_balance.as_decimal(),
+        payload.closed.unwrap_or(existing.closed),
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    let account = fetch_account(&pool, &user, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Account not found"))?;
+
+    Ok(Json(account))
+}
+
+async fn delete_account(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM accounts
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await;
+
+    match result {
+        Ok(res) if res.rows_affected() == 0 => Err(AppError::not_found("Account not found")),
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        // Accounts that still have transactions can't be deleted; close them instead
+        Err(sqlx::Error::Database(e)) if e.constraint() == Some("fk_recurring_account") => {
+            Err(AppError::conflict("The account still has recurring transactions; delete or move them first"))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            Err(AppError::conflict("The account still has transactions; close it instead"))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Lists the account's transactions, newest first, with the balance after each one.
+async fn get_ledger(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession
+) -> Result<impl IntoResponse, AppError> {
+    if fetch_account(&pool, &user, id).await?.is_none() {
+        return Err(AppError::not_found("Account not found"));
+    }
+
+    let mut rows: Vec<LedgerEntry> = sqlx::query!(
+        r#"
+        SELECT
+            t.id AS transaction_id, t.description, t.amount, t.currency, t.account_id, t.kind,
+            ROUND(t.amount * exchange_rate_on(t.user_id, t.currency, u.base_currency, t.created_at), 4) AS base_amount,
+            t.created_at AS transaction_created_at,
+            transaction_tag_names(t.id) AS "tags!",
+            c.id AS "category_id?", c.name AS "category_name?", c.created_at AS "category_created_at?",
+            ch.parent_id as "parent_id?",
+            tr.id as "transfer_id?",
+            fa.id as "from_account_id?", fa.name as "from_account_name?",
+            ta.id as "to_account_id?", ta.name as "to_account_name?",
+            a.opening_balance + SUM(t.amount) OVER (ORDER BY t.created_at, t.id) AS "running_balance!"
+        FROM transactions t
+        JOIN accounts a ON t.account_id = a.id
+        JOIN users u ON t.user_id = u.id
+        LEFT JOIN categories c ON t.category_id = c.id
+        LEFT JOIN category_hierarchy ch ON c.id = ch.category_id
+        LEFT JOIN transfers tr ON t.transfer_id = tr.id
+        LEFT JOIN accounts fa ON tr.from_account_id = fa.id
+        LEFT JOIN accounts ta ON tr.to_account_id = ta.id
+        WHERE t.account_id = $1 AND t.user_id = $2
+        ORDER BY t.created_at DESC, t.id DESC
+        "#,
+        id,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| LedgerEntry {
+        transaction: Transaction {
+            id: row.transaction_id,
+            category: Category::from_columns(
+                row.category_id,
+                row.category_name,
+                row.parent_id,
+                row.category_created_at.map(convert_time_to_chrono)
+            ),
+            account_id: row.account_id,
+            transfer: TransferLink::from_columns(
+                row.transfer_id,
+                row.from_account_id,
+                row.from_account_name,
+                row.to_account_id,
+                row.to_account_name
+            ),
+            kind: TransactionKind::from_db(&row.kind),
+            description: row.description,
+            amount: row.amount.into(),
+            currency: row.currency.into(),
+            base_amount: row.base_amount.map(Into::into),
+            tags: row.tags,
+            splits: Vec::new(),
+            created_at: convert_time_to_chrono(row.transaction_created_at),
+        },
+        running_balance: row.running_balance.into(),
+    })
+    .collect();
+
+    attach_splits(&pool, rows.iter_mut().map(|entry| &mut entry.transaction)).await?;
+
+    Ok(Json(rows))
+}
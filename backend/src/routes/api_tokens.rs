@@ -0,0 +1,128 @@
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::{delete, get}, Extension, Json, Router};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::{AuthMethod, AuthSession},
+    models::api_token::{ApiToken, CreatedApiToken, NewApiToken, TokenScope},
+    passwords::hash_password,
+    time_conversion::{convert_chrono_to_time, convert_time_to_chrono},
+    tokens::{format_api_token, generate_token}
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/me/tokens", get(list_tokens).post(create_token))
+        .route("/me/tokens/{id}", delete(delete_token))
+}
+
+async fn list_tokens(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let rows: Vec<ApiToken> = sqlx::query!(
+        r#"
+        SELECT id, name, scope, created_at, last_used_at, expires_at
+        FROM api_tokens
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| ApiToken {
+        id: row.id,
+        name: row.name,
+        scope: TokenScope::from_db(&row.scope),
+        created_at: convert_time_to_chrono(row.created_at),
+        last_used_at: row.last_used_at.map(convert_time_to_chrono),
+        expires_at: row.expires_at.map(convert_time_to_chrono),
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+async fn create_token(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Extension(auth_method): Extension<AuthMethod>,
+    Json(payload): Json<NewApiToken>,
+) -> Result<impl IntoResponse, AppError> {
+    auth_method.require_session("API tokens cannot create other tokens")?;
+
+    if payload.name.trim().is_empty() {
+        return Err(AppError::bad_request("Token name is required").with_field("name", "Required"));
+    }
+
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(AppError::bad_request("Expiry must be in the future").with_field("expires_at", "Must be in the future"));
+    }
+
+    let secret = generate_token();
+    let secret_hash = hash_password(&secret)
+        .map_err(|_| AppError::internal())?;
+
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO api_tokens (user_id, name, secret_hash, scope, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, name) DO NOTHING
+        RETURNING id, name, scope, created_at, last_used_at, expires_at
+        "#,
+        user.id,
+        payload.name.trim(),
+        secret_hash,
+        payload.scope.as_str(),
+        payload.expires_at.map(convert_chrono_to_time)
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    let Some(record) = record else {
+        return Err(AppError::conflict("A token with this name already exists").with_field("name", "Already in use"));
+    };
+
+    let created = CreatedApiToken {
+        token: format_api_token(record.id, &secret),
+        api_token: ApiToken {
+            id: record.id,
+            name: record.name,
+            scope: TokenScope::from_db(&record.scope),
+            created_at: convert_time_to_chrono(record.created_at),
+            last_used_at: record.last_used_at.map(convert_time_to_chrono),
+            expires_at: record.expires_at.map(convert_time_to_chrono),
+        },
+    };
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+async fn delete_token(
+    Path(id): Path<Uuid>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Extension(auth_method): Extension<AuthMethod>,
+) -> Result<StatusCode, AppError> {
+    auth_method.require_session("API tokens cannot revoke tokens")?;
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM api_tokens
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Token not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
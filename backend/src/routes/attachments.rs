@@ -0,0 +1,346 @@
+use axum::{
+    body::Bytes,
+    extract::{multipart::MultipartError, DefaultBodyLimit, Multipart, Path},
+    http::{header::{CONTENT_DISPOSITION, CONTENT_TYPE}, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::AuthSession,
+    models::{attachment::Attachment, user::User},
+    storage::Storage,
+    time_conversion::convert_time_to_chrono
+};
+
+/// Phone photos of receipts stay well under this.
+const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// Room for the multipart boundaries and headers around the file.
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route(
+            "/transactions/{id}/attachments",
+            get(list_attachments)
+                .post(upload_attachment)
+                .layer(DefaultBodyLimit::max(MAX_ATTACHMENT_SIZE + MULTIPART_OVERHEAD)),
+        )
+        .route(
+            "/transactions/{id}/attachments/{attachment_id}",
+            get(download_attachment).delete(delete_attachment),
+        )
+}
+
+/// Works out the type of an uploaded file from its first bytes, rather than trusting the client.
+/// Only receipts and documents are accepted: PDF, JPEG, PNG, WebP and HEIC.
+fn detect_content_type(contents: &[u8]) -> Option<&'static str> {
+    match contents {
+        [b'%', b'P', b'D', b'F', b'-', ..] => Some("application/pdf"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n', ..] => Some("image/png"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] if [b"heic", b"heix", b"mif1"].iter().any(|b| brand.starts_with(*b)) => {
+            Some("image/heic")
+        }
+        _ => None,
+    }
+}
+
+/// Keeps the last path component of a client-supplied file name, without control characters.
+fn sanitize_file_name(name: Option<&str>) -> String {
+    let name = name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect::<String>();
+
+    match name.trim() {
+        "" => "attachment".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// A `Content-Disposition` header asking the browser to download the file under its original name.
+/// Clients that don't understand `filename*` get an ASCII approximation.
+fn content_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+fn multipart_error(err: MultipartError) -> AppError {
+    if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        return too_large();
+    }
+
+    AppError::bad_request(err.body_text())
+}
+
+fn too_large() -> AppError {
+    AppError::payload_too_large(format!("Attachments can be at most {} MB", MAX_ATTACHMENT_SIZE / (1024 * 1024)))
+        .with_field("file", "Too large")
+}
+
+async fn ensure_transaction_exists(pool: &PgPool, user: &User, transaction_id: i32) -> Result<(), AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT id FROM transactions WHERE id = $1 AND user_id = $2"#,
+        transaction_id,
+        user.id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Transaction not found"))?;
+
+    Ok(())
+}
+
+/// Removes files whose attachment rows are already gone. The rows no longer point at them,
+/// so a file that can't be removed is only logged.
+pub async fn remove_stored_files(storage: &Storage, keys: &[String]) {
+    for key in keys {
+        if let Err(err) = storage.delete(key).await {
+            eprintln!("Failed to remove attachment {}: {}", key, err);
+        }
+    }
+}
+
+async fn list_attachments(
+    Path(transaction_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    ensure_transaction_exists(&pool, &user, transaction_id).await?;
+
+    let rows: Vec<Attachment> = sqlx::query!(
+        r#"
+        SELECT id, transaction_id, file_name, content_type, size_bytes, created_at
+        FROM attachments
+        WHERE transaction_id = $1 AND user_id = $2
+        ORDER BY created_at ASC, id ASC
+        "#,
+        transaction_id,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| Attachment {
+        id: row.id,
+        transaction_id: row.transaction_id,
+        file_name: row.file_name,
+        content_type: row.content_type,
+        size_bytes: row.size_bytes,
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+/// Stores the multipart field named `file` with the transaction.
+async fn upload_attachment(
+    Path(transaction_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Extension(storage): Extension<Storage>,
+    AuthSession(user): AuthSession,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    ensure_transaction_exists(&pool, &user, transaction_id).await?;
+
+    let mut upload = None;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = sanitize_file_name(field.file_name());
+        let mut contents = Vec::new();
+
+        while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+            if contents.len() + chunk.len() > MAX_ATTACHMENT_SIZE {
+                return Err(too_large());
+            }
+            contents.extend_from_slice(&chunk);
+        }
+
+        upload = Some((file_name, contents));
+        break;
+    }
+
+    let (file_name, contents) = upload
+        .ok_or_else(|| AppError::validation("No file was uploaded").with_field("file", "Required"))?;
+
+    if contents.is_empty() {
+        return Err(AppError::validation("The file is empty").with_field("file", "Cannot be empty"));
+    }
+
+    let content_type = detect_content_type(&contents).ok_or_else(|| {
+        AppError::validation("Only PDF, JPEG, PNG, WebP and HEIC files can be attached")
+            .with_field("file", "Unsupported file type")
+    })?;
+
+    let size_bytes = contents.len() as i64;
+    let storage_key = format!("{}/{}", user.id, Uuid::new_v4());
+
+    storage.put(&storage_key, Bytes::from(contents)).await.map_err(|err| {
+        eprintln!("Failed to store attachment {}: {}", storage_key, err);
+        AppError::internal()
+    })?;
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO attachments (transaction_id, user_id, file_name, content_type, size_bytes, storage_key)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, created_at
+        "#,
+        transaction_id,
+        user.id,
+        file_name,
+        content_type,
+        size_bytes,
+        storage_key
+    )
+    .fetch_one(&pool)
+    .await;
+
+    // The transaction may have been deleted while the file was uploading
+    let record = match inserted {
+        Ok(record) => record,
+        Err(err) => {
+            remove_stored_files(&storage, &[storage_key]).await;
+            return Err(err.into());
+        }
+    };
+
+    let attachment = Attachment {
+        id: record.id,
+        transaction_id,
+        file_name,
+        content_type: content_type.to_string(),
+        size_bytes,
+        created_at: convert_time_to_chrono(record.created_at),
+    };
+
+    Ok((StatusCode::CREATED, Json(attachment)))
+}
+
+async fn download_attachment(
+    Path((transaction_id, id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+    Extension(storage): Extension<Storage>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let attachment = sqlx::query!(
+        r#"
+        SELECT file_name, content_type, storage_key
+        FROM attachments
+        WHERE id = $1 AND transaction_id = $2 AND user_id = $3
+        "#,
+        id,
+        transaction_id,
+        user.id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Attachment not found"))?;
+
+    let contents = storage.get(&attachment.storage_key).await.map_err(|err| {
+        eprintln!("Failed to read attachment {}: {}", attachment.storage_key, err);
+        AppError::internal()
+    })?;
+
+    let headers = [
+        (CONTENT_TYPE, attachment.content_type),
+        (CONTENT_DISPOSITION, content_disposition(&attachment.file_name)),
+    ];
+
+    Ok((headers, contents))
+}
+
+async fn delete_attachment(
+    Path((transaction_id, id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+    Extension(storage): Extension<Storage>,
+    AuthSession(user): AuthSession,
+) -> Result<StatusCode, AppError> {
+    let storage_key = sqlx::query_scalar!(
+        r#"
+        DELETE FROM attachments
+        WHERE id = $1 AND transaction_id = $2 AND user_id = $3
+        RETURNING storage_key
+        "#,
+        id,
+        transaction_id,
+        user.id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Attachment not found"))?;
+
+    remove_stored_files(&storage, &[storage_key]).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_supported_types() {
+        assert_eq!(detect_content_type(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(detect_content_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10]), Some("image/jpeg"));
+        assert_eq!(detect_content_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(detect_content_type(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(detect_content_type(b"\0\0\0\x18ftypheic\0\0\0\0"), Some("image/heic"));
+    }
+
+    #[test]
+    fn test_rejects_other_types() {
+        assert_eq!(detect_content_type(b"<html><script>"), None);
+        assert_eq!(detect_content_type(b"MZ\x90\0"), None);
+        assert_eq!(detect_content_type(b"\0\0\0\x18ftypisom"), None);
+        assert_eq!(detect_content_type(b""), None);
+    }
+
+    #[test]
+    fn test_sanitizes_file_names() {
+        assert_eq!(sanitize_file_name(Some("C:\\Users\\me\\receipt.pdf")), "receipt.pdf");
+        assert_eq!(sanitize_file_name(Some("../../etc/passwd")), "passwd");
+        assert_eq!(sanitize_file_name(Some("bad\r\nname.png")), "badname.png");
+        assert_eq!(sanitize_file_name(Some("  ")), "attachment");
+        assert_eq!(sanitize_file_name(None), "attachment");
+    }
+
+    #[test]
+    fn test_encodes_content_disposition() {
+        assert_eq!(
+            content_disposition("Café \"receipt\".pdf"),
+            "attachment; filename=\"Caf_ _receipt_.pdf\"; filename*=UTF-8''Caf%C3%A9%20%22receipt%22.pdf"
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use axum::{extract::{Path, Query}, http::StatusCode, response::IntoResponse, routing::{get, put}, Extension, Json, Router};
+use chrono::{Months, NaiveDate};
+use sqlx::{types::BigDecimal, PgPool};
+
+use crate::{
+    error::AppError,
+    middleware::AuthSession,
+    models::budget::{Budget, BudgetFilter, BudgetReport, BudgetReportLine, BudgetUpdate, NewBudget},
+    time_conversion::{convert_chrono_to_date, convert_date_to_chrono, convert_time_to_chrono, format_month, parse_month}
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/budgets", get(list_budgets).post(create_budget))
+        .route("/budgets/{month}", get(budget_report))
+        .route("/budgets/{month}/{category_id}", put(set_budget).delete(delete_budget))
+}
+
+async fn list_budgets(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(filter): Query<BudgetFilter>,
+) -> Result<impl IntoResponse, AppError> {
+    let period = match filter.month.as_deref().map(parse_month) {
+        Some(None) => return Err(AppError::bad_request("Month must be formatted as YYYY-MM")),
+        Some(Some(period)) => Some(period),
+        None => None,
+    };
+
+    let budgets: Vec<Budget> = sqlx::query!(
+        r#"
+        SELECT id, category_id, period, amount, rollover, created_at
+        FROM budgets
+        WHERE user_id = $1
+        AND ($2::date IS NULL OR period = $2)
+        ORDER BY period DESC, category_id ASC
+        "#,
+        user.id,
+        period.map(convert_chrono_to_date)
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| Budget {
+        id: row.id,
+        category_id: row.category_id,
+        month: format_month(convert_date_to_chrono(row.period)),
+        amount: row.amount.into(),
+        rollover: row.rollover,
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(budgets))
+}
+
+async fn create_budget(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewBudget>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(period) = parse_month(&payload.month) else {
+        return Err(AppError::bad_request("Month must be formatted as YYYY-MM"));
+    };
+
+    if payload.amount.is_negative() {
+        return Err(AppError::bad_request("Budget amount cannot be negative"));
+    }
+
+    let category = sqlx::query!(
+        r#"SELECT id FROM categories WHERE id = $1 AND user_id = $2"#,
+        payload.category_id,
+        user.id
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    if category.is_none() {
+        return Err(AppError::not_found("Category not found"));
+    }
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO budgets (user_id, category_id, period, amount, rollover)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (category_id, period) DO NOTHING
+        RETURNING id, category_id, period, amount, rollover, created_at
+        "#,
+        user.id,
+        payload.category_id,
+        convert_chrono_to_date(period),
+        payload.amount.as_decimal(),
+        payload.rollover
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Err(AppError::conflict("Category already has a budget for this month"));
+    };
+
+    Ok((StatusCode::CREATED, Json(Budget {
+        id: row.id,
+        category_id: row.category_id,
+        month: format_month(convert_date_to_chrono(row.period)),
+        amount: row.amount.into(),
+        rollover: row.rollover,
+        created_at: convert_time_to_chrono(row.created_at),
+    })))
+}
+
+/// Creates or replaces the budget of a category for a month.
+async fn set_budget(
+    Path((month, category_id)): Path<(String, i32)>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<BudgetUpdate>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(period) = parse_month(&month) else {
+        return Err(AppError::bad_request("Month must be formatted as YYYY-MM"));
+    };
+
+    if payload.amount.is_negative() {
+        return Err(AppError::bad_request("Budget amount cannot be negative"));
+    }
+
+    let category = sqlx::query!(
+        r#"SELECT id FROM categories WHERE id = $1 AND user_id = $2"#,
+        category_id,
+        user.id
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    if category.is_none() {
+        return Err(AppError::not_found("Category not found"));
+    }
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO budgets (user_id, category_id, period, amount, rollover)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (category_id, period) DO UPDATE
+        SET amount = EXCLUDED.amount, rollover = EXCLUDED.rollover
+        RETURNING id, category_id, period, amount, rollover, created_at
+        "#,
+        user.id,
+        category_id,
+        convert_chrono_to_date(period),
+        payload.amount.as_decimal(),
+        payload.rollover
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(Budget {
+        id: row.id,
+        category_id: row.category_id,
+        month: format_month(convert_date_to_chrono(row.period)),
+        amount: row.amount.into(),
+        rollover: row.rollover,
+        created_at: convert_time_to_chrono(row.created_at),
+    }))
+}
+
+async fn delete_budget(
+    Path((month, category_id)): Path<(String, i32)>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(period) = parse_month(&month) else {
+        return Err(AppError::bad_request("Month must be formatted as YYYY-MM"));
+    };
+
+    let result = sqlx::query!(
+        r#"DELETE FROM budgets WHERE category_id = $1 AND period = $2 AND user_id = $3"#,
+        category_id,
+        convert_chrono_to_date(period),
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Budget not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A budget as seen by the rollover calculation.
+struct BudgetPeriod {
+    period: NaiveDate,
+    amount: BigDecimal,
+    rollover: bool,
+}
+
+/// Works out how much is left over from the budgets leading up to `month`.
+/// `history` holds one category's budgets before `month`, oldest first. Each budget that has
+/// rollover enabled picks up what was left of the month before it; a month without a budget
+/// breaks the chain.
+fn carry_into(history: &[BudgetPeriod], spent: &HashMap<NaiveDate, BigDecimal>, month: NaiveDate) -> BigDecimal {
+    let zero = BigDecimal::from(0);
+    let mut carry = zero.clone();
+    let mut previous: Option<NaiveDate> = None;
+
+    for budget in history {
+        let consecutive = previous.and_then(|p| p.checked_add_months(Months::new(1))) == Some(budget.period);
+        let carried = if budget.rollover && consecutive { carry.clone() } else { zero.clone() };
+        let spent = spent.get(&budget.period).unwrap_or(&zero);
+
+        carry = &budget.amount + carried - spent;
+        previous = Some(budget.period);
+    }
+
+    match previous.and_then(|p| p.checked_add_months(Months::new(1))) {
+        Some(next) if next == month => carry,
+        _ => zero,
+    }
+}
+
+/// Budgeted, spent and remaining amounts for every budget in a month, in the user's base currency.
+/// Spending in a category counts towards its own budget and the budgets of all its ancestors.
+async fn budget_report(
+    Path(month): Path<String>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(period) = parse_month(&month) else {
+        return Err(AppError::bad_request("Month must be formatted as YYYY-MM"));
+    };
+
+    let budgets = sqlx::query!(
+        r#"
+        SELECT b.id, b.category_id, c.name AS category_name, b.period, b.amount, b.rollover
+        FROM budgets b
+        JOIN categories c ON b.category_id = c.id
+        WHERE b.user_id = $1 AND b.period <= $2
+        ORDER BY c.name ASC, b.category_id ASC, b.period ASC
+        "#,
+        user.id,
+        convert_chrono_to_date(period)
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let spending = sqlx::query!(
+        r#"
+        WITH RECURSIVE category_tree AS (
+            -- Base case: every category budgeted up to the requested month
+            SELECT DISTINCT category_id AS root_id, category_id AS id
+            FROM budgets
+            WHERE user_id = $1 AND period <= $2
+            UNION ALL
+            -- Recursive step: find all children of the categories in the tree
+            SELECT ct.root_id, ch.category_id
+            FROM category_hierarchy ch
+            INNER JOIN category_tree ct ON ch.parent_id = ct.id
+        )
+        SELECT
+            ct.root_id AS "category_id!",
+            date_trunc('month', t.created_at AT TIME ZONE 'UTC')::date AS "period!",
+            ROUND(COALESCE(-SUM(t.base_amount), 0), 4) AS "spent!",
+            COUNT(*) FILTER (WHERE t.base_amount IS NULL) AS "unconverted_count!"
+        FROM converted_categorized_transactions t
+        JOIN category_tree ct ON t.category_id = ct.id
+        WHERE t.user_id = $1
+        AND t.kind = 'expense'
+        AND (t.created_at AT TIME ZONE 'UTC')::date < $2::date + interval '1 month'
+        GROUP BY 1, 2
+        "#,
+        user.id,
+        convert_chrono_to_date(period)
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut spent_by_category: HashMap<i32, HashMap<NaiveDate, BigDecimal>> = HashMap::new();
+    let mut unconverted_by_category: HashMap<i32, i64> = HashMap::new();
+    for row in spending {
+        let row_period = convert_date_to_chrono(row.period);
+        if row_period == period {
+            unconverted_by_category.insert(row.category_id, row.unconverted_count);
+        }
+        spent_by_category
+            .entry(row.category_id)
+            .or_default()
+            .insert(row_period, row.spent);
+    }
+
+    let mut history: HashMap<i32, Vec<BudgetPeriod>> = HashMap::new();
+    let mut lines = Vec::new();
+    let no_spending = HashMap::new();
+
+    for row in budgets {
+        let budget_period = convert_date_to_chrono(row.period);
+        let spent = spent_by_category.get(&row.category_id).unwrap_or(&no_spending);
+
+        if budget_period < period {
+            history.entry(row.category_id).or_default().push(BudgetPeriod {
+                period: budget_period,
+                amount: row.amount,
+                rollover: row.rollover,
+            });
+            continue;
+        }
+
+        let carried_over = if row.rollover {
+            let previous = history.get(&row.category_id).map(Vec::as_slice).unwrap_or_default();
+            carry_into(previous, spent, period)
+        } else {
+            BigDecimal::from(0)
+        };
+        let spent = spent.get(&period).cloned().unwrap_or_default();
+        let remaining = &row.amount + &carried_over - &spent;
+
+        lines.push(BudgetReportLine {
+            budget_id: row.id,
+            category_id: row.category_id,
+            category_name: row.category_name,
+            rollover: row.rollover,
+            budgeted: row.amount.into(),
+            carried_over: carried_over.into(),
+            spent: spent.into(),
+            remaining: remaining.into(),
+            unconverted_count: unconverted_by_category.get(&row.category_id).copied().unwrap_or(0),
+        });
+    }
+
+    Ok(Json(BudgetReport {
+        month: format_month(period),
+        currency: user.base_currency,
+        budgets: lines,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn month(value: &str) -> NaiveDate {
+        parse_month(value).unwrap()
+    }
+
+    fn amount(value: i32) -> BigDecimal {
+        BigDecimal::from(value)
+    }
+
+    fn budget(period: &str, value: i32, rollover: bool) -> BudgetPeriod {
+        BudgetPeriod { period: month(period), amount: amount(value), rollover }
+    }
+
+    #[test]
+    fn test_carries_remaining_amount_forward() {
+        let history = vec![budget("2024-01", 100, false), budget("2024-02", 100, true)];
+        let spent = HashMap::from([(month("2024-01"), amount(70)), (month("2024-02"), amount(50))]);
+
+        // January leaves 30 which February picks up, leaving 80 for March
+        assert_eq!(carry_into(&history, &spent, month("2024-03")), amount(80));
+    }
+
+    #[test]
+    fn test_carries_overspending_forward() {
+        let history = vec![budget("2024-01", 100, false)];
+        let spent = HashMap::from([(month("2024-01"), amount(150))]);
+
+        assert_eq!(carry_into(&history, &spent, month("2024-02")), amount(-50));
+    }
+
+    #[test]
+    fn test_gap_in_budgets_resets_carry() {
+        let history = vec![budget("2024-01", 100, false), budget("2024-03", 100, true)];
+        let spent = HashMap::new();
+
+        // Nothing carries from January into March, and March doesn't directly precede May
+        assert_eq!(carry_into(&history, &spent, month("2024-04")), amount(100));
+        assert_eq!(carry_into(&history, &spent, month("2024-05")), amount(0));
+    }
+}
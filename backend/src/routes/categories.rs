@@ -1,19 +1,22 @@
-use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router};
+use std::collections::HashMap;
+
+use axum::{extract::{Path, Query}, http::StatusCode, response::IntoResponse, routing::{get, post}, Extension, Json, Router};
 use sqlx::PgPool;
-use crate::{middleware::AuthSession, models::{category::{Category, NewCategory}, transaction::Transaction}, time_conversion::convert_time_to_chrono};
-use bigdecimal::ToPrimitive;
+use crate::{error::AppError, middleware::AuthSession, models::{category::{Category, CategoryMerge, CategoryTransaction, CategoryMergeResult, CategoryTreeNode, DeleteCategoryQuery, DeleteStrategy, NewCategory}, report::ReportRange, transaction::{Transaction, TransactionKind}, user::User}, routes::transactions::attach_splits, time_conversion::{convert_chrono_to_date, convert_time_to_chrono}};
 use futures::future::join_all;
 
 pub fn routes() -> Router {
     Router::new().route("/categories", get(list_categories).post(create_category))
+        .route("/categories/tree", get(get_category_tree))
         .route("/categories/{id}", get(get_category).put(update_category).delete(delete_category))
         .route("/categories/{id}/transactions", get(get_transactions))
+        .route("/categories/{id}/merge", post(merge_category))
 }
 
 async fn list_categories(
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, AppError> {
     let futures = sqlx::query!(
         r#"
         SELECT c.id, c.name, c.created_at, ch.parent_id as "parent_id?"
@@ -25,8 +28,7 @@ async fn list_categories(
         user.id
     )
     .fetch_all(&pool)
-    .await
-    .expect("Failed to fetch categories")
+    .await?
     .into_iter()
     .map(async |row| Category {
         id: row.id,
@@ -37,15 +39,119 @@ async fn list_categories(
 
     let rows: Vec<Category> = join_all(futures).await;
 
-    Json(rows)
+    Ok(Json(rows))
+}
+
+/// Nests categories under their parents. `nodes` must be sorted in the order siblings should appear.
+fn build_tree(nodes: Vec<CategoryTreeNode>) -> Vec<CategoryTreeNode> {
+    let mut children_of: HashMap<Option<i32>, Vec<CategoryTreeNode>> = HashMap::new();
+    for node in nodes {
+        children_of.entry(node.parent_id).or_default().push(node);
+    }
+
+    fn attach(parent_id: Option<i32>, children_of: &mut HashMap<Option<i32>, Vec<CategoryTreeNode>>) -> Vec<CategoryTreeNode> {
+        let mut children = children_of.remove(&parent_id).unwrap_or_default();
+        for child in &mut children {
+            child.children = attach(Some(child.id), children_of);
+        }
+        children
+    }
+
+    attach(None, &mut children_of)
+}
+
+/// Every category nested under its parent, with transaction counts and totals for an optional date range.
+/// Transfers are left out since they don't belong to a category.
+async fn get_category_tree(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(range): Query<ReportRange>,
+) -> Result<impl IntoResponse, AppError> {
+    let nodes = sqlx::query!(
+        r#"
+        WITH RECURSIVE tree AS (
+            -- Base case: top-level categories
+            SELECT c.id, c.name, NULL::int AS parent_id, 0 AS depth, c.name AS path
+            FROM categories c
+            WHERE c.user_id = $1
+            AND NOT EXISTS (SELECT 1 FROM category_hierarchy ch WHERE ch.category_id = c.id)
+            UNION ALL
+            -- Recursive step: their children, one level deeper
+            SELECT c.id, c.name, t.id, t.depth + 1, t.path || ' / ' || c.name
+            FROM tree t
+            INNER JOIN category_hierarchy ch ON ch.parent_id = t.id
+            INNER JOIN categories c ON c.id = ch.category_id
+        ),
+        converted AS (
+            SELECT t.category_id, t.base_amount
+            FROM converted_categorized_transactions t
+            WHERE t.user_id = $1
+            AND t.kind <> 'transfer'
+            AND ($2::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date >= $2)
+            AND ($3::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date <= $3)
+        ),
+        own AS (
+            SELECT category_id, COUNT(*) AS own_count, SUM(base_amount) AS own_total
+            FROM converted
+            GROUP BY category_id
+        ),
+        rolled_up AS (
+            SELECT
+                a.ancestor_id AS category_id,
+                COUNT(*) AS total_count,
+                SUM(cv.base_amount) AS total,
+                COUNT(*) FILTER (WHERE cv.base_amount IS NULL) AS unconverted_count
+            FROM converted cv
+            JOIN category_ancestry($1) a ON a.category_id = cv.category_id
+            GROUP BY a.ancestor_id
+        )
+        SELECT
+            tree.id AS "id!",
+            tree.name AS "name!",
+            tree.parent_id,
+            tree.depth AS "depth!",
+            tree.path AS "path!",
+            COALESCE(own.own_count, 0) AS "own_count!",
+            ROUND(COALESCE(own.own_total, 0), 4) AS "own_total!",
+            COALESCE(rolled_up.total_count, 0) AS "total_count!",
+            ROUND(COALESCE(rolled_up.total, 0), 4) AS "total!",
+            COALESCE(rolled_up.unconverted_count, 0) AS "unconverted_count!"
+        FROM tree
+        LEFT JOIN own ON own.category_id = tree.id
+        LEFT JOIN rolled_up ON rolled_up.category_id = tree.id
+        ORDER BY tree.name ASC
+        "#,
+        user.id,
+        range.from.map(convert_chrono_to_date),
+        range.to.map(convert_chrono_to_date)
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| CategoryTreeNode {
+        id: row.id,
+        name: row.name,
+        parent_id: row.parent_id,
+        depth: row.depth,
+        path: row.path,
+        own_count: row.own_count,
+        own_total: row.own_total.into(),
+        total_count: row.total_count,
+        total: row.total.into(),
+        unconverted_count: row.unconverted_count,
+        children: Vec::new(),
+    })
+    .collect();
+
+    Ok(Json(build_tree(nodes)))
 }
 
 pub async fn create_category(
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession,
     Json(payload): Json<NewCategory>,
-) -> Json<Category> {
-    let mut tx = pool.begin().await.expect("Failed to begin transaction");
+) -> Result<Json<Category>, AppError> {
+    let mut tx = pool.begin().await?;
 
     let record = sqlx::query!(
         r#"
@@ -57,8 +163,7 @@ pub async fn create_category(
         payload.name,
     )
     .fetch_one(&mut *tx)
-    .await
-    .expect("Failed to insert transaction");
+    .await?;
 
     if let Some(parent_id) = payload.parent_id {
         sqlx::query!(
@@ -71,11 +176,10 @@ pub async fn create_category(
             user.id
         )
         .execute(&mut *tx)
-        .await
-        .expect("Failed to insert hierarchy link");
+        .await?;
     }
 
-    tx.commit().await.expect("Failed to commit transaction");
+    tx.commit().await?;
 
     let result = Category {
         id: record.id,
@@ -84,14 +188,14 @@ pub async fn create_category(
         created_at: convert_time_to_chrono(record.created_at)
     };
 
-    Json(result)
+    Ok(Json(result))
 }
 
 async fn get_category(
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, AppError> {
     let existing = sqlx::query!(
         r#"
         SELECT c.id, c.name, c.created_at, ch.parent_id as "parent_id?"
@@ -103,14 +207,11 @@ async fn get_category(
         user.id
     )
     .fetch_optional(&pool)
-    .await
-    .expect("Failed to fetch category");
-
-    if existing.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    .await?;
 
-    let row = existing.unwrap();
+    let Some(row) = existing else {
+        return Err(AppError::not_found("Category not found"));
+    };
 
     let category = Category {
         id: row.id,
@@ -127,14 +228,14 @@ async fn update_category(
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession,
     Json(payload): Json<NewCategory>,
-) -> impl IntoResponse {
-    let mut tx = pool.begin().await.expect("Failed to begin transaction");
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
 
     // Check for circular dependencies BEFORE we update anything
     if let Some(pid) = payload.parent_id {
         if pid == id {
-            // Cannot parent to itself
-            return Err(StatusCode::BAD_REQUEST);
+            return Err(AppError::validation("A category cannot be its own parent")
+                .with_field("parent_id", "A category cannot be its own parent"));
         }
 
         let is_descendant = sqlx::query!(
@@ -151,12 +252,12 @@ async fn update_category(
             pid
         )
         .fetch_optional(&mut *tx)
-        .await
-        .expect("Failed to check descendants");
+        .await?;
 
         if is_descendant.is_some() {
             // Cannot parent to a descendant (circular loop)
-            return Err(StatusCode::BAD_REQUEST);
+            return Err(AppError::validation("A category cannot be moved under one of its subcategories")
+                .with_field("parent_id", "Would create a loop"));
         }
     }
 
@@ -170,13 +271,11 @@ async fn update_category(
         user.id
     )
     .fetch_optional(&mut *tx)
-    .await
-    .expect("Failed to update category");
+    .await?;
 
-    if row.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
-    let row = row.unwrap();
+    let Some(row) = row else {
+        return Err(AppError::not_found("Category not found"));
+    };
 
     match payload.parent_id {
         Some(parent_id) => {
@@ -192,8 +291,7 @@ async fn update_category(
                 user.id
             )
             .execute(&mut *tx)
-            .await
-            .expect("Failed to update hierarchy link");
+            .await?;
         }
         None => {
             sqlx::query!(
@@ -201,12 +299,11 @@ async fn update_category(
                 id
             )
             .execute(&mut *tx)
-            .await
-            .expect("Failed to delete hierarchy link");
+            .await?;
         }
     }
 
-    tx.commit().await.expect("Failed to commit transaction");
+    tx.commit().await?;
 
     let updated = Category {
         id: row.id,
@@ -218,54 +315,305 @@ async fn update_category(
     Ok(Json(updated))
 }
 
+/// Deletes a category. Without a strategy this is refused while transactions, subcategories or
+/// recurring transactions still use it; the response says how many of each are in the way.
+/// `strategy=reassign&target=..` first moves all of them to `target`, and
+/// `strategy=promote_children` moves the subcategories up to the category's own parent.
 async fn delete_category(
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
-    AuthSession(user): AuthSession
-) -> impl IntoResponse {
-    // 1. Check if this category is a parent to any children
-    let has_children = sqlx::query!(
+    AuthSession(user): AuthSession,
+    Query(query): Query<DeleteCategoryQuery>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let category = sqlx::query!(
         r#"
-        SELECT 1 AS exists 
-        FROM category_hierarchy 
-        WHERE parent_id = $1
-        LIMIT 1
+        SELECT c.id, ch.parent_id AS "parent_id?"
+        FROM categories c
+        LEFT JOIN category_hierarchy ch ON c.id = ch.category_id
+        WHERE c.id = $1 AND c.user_id = $2
         "#,
-        id
+        id,
+        user.id
     )
-    .fetch_optional(&pool)
-    .await
-    .expect("Failed to check for child categories");
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    if has_children.is_some() {
-        return Err(StatusCode::CONFLICT);
+    let Some(category) = category else {
+        return Err(AppError::not_found("Category not found"));
+    };
+
+    match query.strategy {
+        Some(DeleteStrategy::Reassign) => {
+            let Some(target_id) = query.target else {
+                return Err(AppError::bad_request("Reassigning needs a target category")
+                    .with_field("target", "Required with strategy=reassign"));
+            };
+
+            move_category_contents(&mut tx, &user, id, target_id).await?;
+        }
+        Some(DeleteStrategy::PromoteChildren) => {
+            match category.parent_id {
+                Some(parent_id) => sqlx::query!(
+                    r#"UPDATE category_hierarchy SET parent_id = $1 WHERE parent_id = $2 AND user_id = $3"#,
+                    parent_id,
+                    id,
+                    user.id
+                )
+                .execute(&mut *tx)
+                .await?,
+                // Children of a top-level category become top-level themselves
+                None => sqlx::query!(
+                    r#"DELETE FROM category_hierarchy WHERE parent_id = $1 AND user_id = $2"#,
+                    id,
+                    user.id
+                )
+                .execute(&mut *tx)
+                .await?,
+            };
+        }
+        None => {}
     }
 
-    // 2. If no children exist, proceed with deletion
-    let result = sqlx::query!(
+    let blockers = sqlx::query!(
         r#"
-        DELETE FROM categories
-        WHERE id = $1 AND user_id = $2
+        SELECT
+            (
+                SELECT COUNT(*) FROM transactions
+                WHERE category_id = $1 OR id IN (SELECT transaction_id FROM transaction_splits WHERE category_id = $1)
+            ) AS "transactions!",
+            (SELECT COUNT(*) FROM category_hierarchy WHERE parent_id = $1) AS "children!",
+            (SELECT COUNT(*) FROM recurring_transactions WHERE category_id = $1) AS "recurring_transactions!"
         "#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if blockers.transactions > 0 || blockers.children > 0 || blockers.recurring_transactions > 0 {
+        let in_use = [
+            ("transactions", blockers.transactions, "transactions"),
+            ("children", blockers.children, "subcategories"),
+            ("recurring_transactions", blockers.recurring_transactions, "recurring transactions"),
+        ];
+        let blocked = in_use
+            .into_iter()
+            .filter(|(_, count, _)| *count > 0)
+            .fold(AppError::conflict("Category is still in use"), |error, (field, count, noun)| {
+                error.with_field(field, format!("{} {} still use this category", count, noun))
+            });
+        return Err(blocked);
+    }
+
+    sqlx::query!(
+        r#"DELETE FROM categories WHERE id = $1 AND user_id = $2"#,
+        id,
+        user.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Folds one category into another: its transactions, subcategories, budgets, spending limit and
+/// recurring transactions move to the target, then the category itself is deleted. All or nothing.
+async fn merge_category(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<CategoryMerge>,
+) -> Result<Json<CategoryMergeResult>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let result = move_category_contents(&mut tx, &user, id, payload.target_id).await?;
+
+    sqlx::query!(
+        r#"DELETE FROM categories WHERE id = $1 AND user_id = $2"#,
         id,
         user.id
     )
-    .execute(&pool)
-    .await;
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(result))
+}
 
-    match result {
-        Ok(res) if res.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
-        Ok(_) => Ok(StatusCode::NO_CONTENT),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+/// Moves everything filed under category `id` over to `target_id`, leaving `id` empty.
+/// Anything left pointing at the source once it's deleted (its own parent link, duplicate alerts) cascades away.
+async fn move_category_contents(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user: &User,
+    id: i32,
+    target_id: i32,
+) -> Result<CategoryMergeResult, AppError> {
+    if target_id == id {
+        return Err(AppError::bad_request("Cannot merge a category into itself"));
     }
+
+    let found = sqlx::query!(
+        r#"SELECT COUNT(*) AS "count!" FROM categories WHERE id IN ($1, $2) AND user_id = $3"#,
+        id,
+        target_id,
+        user.id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    if found.count != 2 {
+        return Err(AppError::not_found("Category not found"));
+    }
+
+    // The source's children are about to become the target's, which would loop if the target is one of them
+    let is_descendant = sqlx::query!(
+        r#"
+        WITH RECURSIVE descendants AS (
+            SELECT category_id FROM category_hierarchy WHERE parent_id = $1
+            UNION ALL
+            SELECT ch.category_id FROM category_hierarchy ch
+            INNER JOIN descendants d ON ch.parent_id = d.category_id
+        )
+        SELECT category_id FROM descendants WHERE category_id = $2
+        "#,
+        id,
+        target_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    if is_descendant.is_some() {
+        return Err(AppError::bad_request("Cannot merge a category into one of its subcategories"));
+    }
+
+    let transactions = sqlx::query!(
+        r#"UPDATE transactions SET category_id = $1 WHERE category_id = $2 AND user_id = $3"#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    let splits = sqlx::query!(
+        r#"UPDATE transaction_splits SET category_id = $1 WHERE category_id = $2 AND user_id = $3"#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    // Profiles would otherwise lose their default when the category is deleted
+    sqlx::query!(
+        r#"UPDATE csv_profiles SET default_category_id = $1 WHERE default_category_id = $2 AND user_id = $3"#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let children = sqlx::query!(
+        r#"UPDATE category_hierarchy SET parent_id = $1 WHERE parent_id = $2 AND user_id = $3"#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    // Months budgeted in both categories add up; the rest move over as they are
+    let combined_budgets = sqlx::query!(
+        r#"
+        WITH combined AS (
+            UPDATE budgets target
+            SET amount = target.amount + source.amount
+            FROM budgets source
+            WHERE source.category_id = $2 AND target.category_id = $1 AND source.period = target.period
+            RETURNING source.id
+        )
+        DELETE FROM budgets WHERE id IN (SELECT id FROM combined)
+        "#,
+        target_id,
+        id
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    let moved_budgets = sqlx::query!(
+        r#"UPDATE budgets SET category_id = $1 WHERE category_id = $2 AND user_id = $3"#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    // The target's own spending limit wins; otherwise it inherits the source's
+    sqlx::query!(
+        r#"
+        UPDATE spending_limits SET category_id = $1
+        WHERE category_id = $2 AND user_id = $3
+        AND NOT EXISTS (SELECT 1 FROM spending_limits WHERE category_id = $1)
+        "#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE notifications n SET category_id = $1
+        WHERE n.category_id = $2 AND n.user_id = $3
+        AND NOT EXISTS (
+            SELECT 1 FROM notifications o
+            WHERE o.category_id = $1 AND o.period = n.period AND o.threshold = n.threshold
+        )
+        "#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let recurring_transactions = sqlx::query!(
+        r#"UPDATE recurring_transactions SET category_id = $1 WHERE category_id = $2 AND user_id = $3"#,
+        target_id,
+        id,
+        user.id
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    Ok(CategoryMergeResult {
+        target_id,
+        transactions: transactions + splits,
+        children,
+        budgets: combined_budgets + moved_budgets,
+        recurring_transactions,
+    })
 }
 
 async fn get_transactions(
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession
-) -> impl IntoResponse {
-    let rows: Vec<Transaction> = sqlx::query!(
+) -> Result<impl IntoResponse, AppError> {
+    let mut rows: Vec<CategoryTransaction> = sqlx::query!(
         r#"
         WITH RECURSIVE category_tree AS (
             -- Base case: the requested category
@@ -275,38 +623,102 @@ async fn get_transactions(
             SELECT ch.category_id
             FROM category_hierarchy ch
             INNER JOIN category_tree ct ON ch.parent_id = ct.id
+        ),
+        -- Only the splits under the category count towards it
+        in_tree AS (
+            SELECT transaction_id, SUM(amount) AS amount
+            FROM categorized_transactions
+            WHERE category_id IN (SELECT id FROM category_tree)
+            GROUP BY transaction_id
         )
         SELECT
-            t.id AS transaction_id, t.description, t.amount, t.created_at AS transaction_created_at,
+            t.id AS transaction_id, t.description, t.amount, t.currency, t.account_id, t.kind,
+            it.amount AS "category_amount!",
+            ROUND(t.amount * exchange_rate_on(t.user_id, t.currency, u.base_currency, t.created_at), 4) AS base_amount,
+            t.created_at AS transaction_created_at,
+            transaction_tag_names(t.id) AS "tags!",
             c.id AS category_id, c.name, c.created_at AS category_created_at,
             ch.parent_id as "parent_id?"
         FROM transactions t
+        JOIN in_tree it ON it.transaction_id = t.id
+        JOIN users u ON t.user_id = u.id
         JOIN categories c ON t.category_id = c.id
         LEFT JOIN category_hierarchy ch ON c.id = ch.category_id
-        WHERE c.id IN (SELECT id FROM category_tree)
-        AND t.user_id = $2
+        WHERE t.user_id = $2
         ORDER BY t.created_at DESC
         "#,
         id,
         user.id
     )
     .fetch_all(&pool)
-    .await
-    .expect("Failed to fetch category transactions")
+    .await?
     .into_iter()
-    .map(|row| Transaction {
-        id: row.transaction_id,
-        category: Category { 
-            id: row.category_id,
-            name: row.name,
-            parent_id: row.parent_id, 
-            created_at: convert_time_to_chrono(row.category_created_at),
+    .map(|row| CategoryTransaction {
+        transaction: Transaction {
+            id: row.transaction_id,
+            category: Some(Category { 
+                id: row.category_id,
+                name: row.name,
+                parent_id: row.parent_id, 
+                created_at: convert_time_to_chrono(row.category_created_at),
+            }),
+            account_id: row.account_id,
+            transfer: None,
+            kind: TransactionKind::from_db(&row.kind),
+            description: row.description,
+            amount: row.amount.into(),
+            currency: row.currency.into(),
+            base_amount: row.base_amount.map(Into::into),
+            tags: row.tags,
+            splits: Vec::new(),
+            created_at: convert_time_to_chrono(row.transaction_created_at)
         },
-        description: row.description,
-        amount: row.amount.to_f64().unwrap_or(0.0),
-        created_at: convert_time_to_chrono(row.transaction_created_at)
+        category_amount: row.category_amount.into(),
     })
     .collect(); 
 
-    Json(rows)
+    attach_splits(&pool, rows.iter_mut().map(|row| &mut row.transaction)).await?;
+
+    Ok(Json(rows))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: i32, parent_id: Option<i32>) -> CategoryTreeNode {
+        CategoryTreeNode {
+            id,
+            name: format!("Category {}", id),
+            parent_id,
+            depth: 0,
+            path: String::new(),
+            own_count: 0,
+            own_total: Default::default(),
+            total_count: 0,
+            total: Default::default(),
+            unconverted_count: 0,
+            children: Vec::new(),
+        }
+    }
+
+    fn ids(nodes: &[CategoryTreeNode]) -> Vec<i32> {
+        nodes.iter().map(|n| n.id).collect()
+    }
+
+    #[test]
+    fn test_nests_children_under_parents() {
+        let tree = build_tree(vec![node(3, Some(1)), node(1, None), node(4, Some(3)), node(2, None), node(5, Some(1))]);
+
+        assert_eq!(ids(&tree), vec![1, 2]);
+        assert_eq!(ids(&tree[0].children), vec![3, 5]);
+        assert_eq!(ids(&tree[0].children[0].children), vec![4]);
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn test_empty_input_gives_empty_tree() {
+        assert!(build_tree(Vec::new()).is_empty());
+    }
 }
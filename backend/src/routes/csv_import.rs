@@ -0,0 +1,305 @@
+use axum::{body::Bytes, extract::{DefaultBodyLimit, Path, Query}, http::StatusCode, response::IntoResponse, routing::{get, post}, Extension, Json, Router};
+use chrono::format::{Item, StrftimeItems};
+use sqlx::{types::BigDecimal, PgPool};
+
+use crate::{
+    alerts::evaluate_spending_limits,
+    csv::parse_csv,
+    currency::Currency,
+    encoding::decode,
+    error::AppError,
+    middleware::AuthSession,
+    models::{csv_import::{CsvImportQuery, CsvImportResult, CsvMapping, CsvProfile, NewCsvProfile}, user::User},
+    time_conversion::{convert_chrono_to_time, convert_time_to_chrono}
+};
+
+/// A few years of statements from a single account.
+const MAX_CSV_FILE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Bank exports have a handful of columns; anything past this is a typo.
+const MAX_COLUMN: usize = 1000;
+
+pub fn routes() -> Router {
+    Router::new().route("/import/csv", post(import_csv).layer(DefaultBodyLimit::max(MAX_CSV_FILE_SIZE)))
+        .route("/import/csv/profiles", get(list_profiles).post(create_profile))
+        .route("/import/csv/profiles/{id}", get(get_profile).put(update_profile).delete(delete_profile))
+}
+
+fn validate_mapping(mapping: &CsvMapping) -> Result<(), AppError> {
+    let invalid = |field, message| Err(AppError::validation("Invalid CSV mapping").with_field(field, message));
+
+    if matches!(mapping.delimiter, '"' | '\n' | '\r') {
+        return invalid("delimiter", "Cannot be a quote or a line break");
+    }
+    if !matches!(mapping.decimal_separator, '.' | ',') {
+        return invalid("decimal_separator", "Must be . or ,");
+    }
+    if mapping.date_format.trim().is_empty() || StrftimeItems::new(&mapping.date_format).any(|item| item == Item::Error) {
+        return invalid("date_format", "Not a valid date format");
+    }
+    if mapping.amount_column.is_some() == (mapping.debit_column.is_some() || mapping.credit_column.is_some()) {
+        return invalid("amount_column", "Set either an amount column or debit and credit columns");
+    }
+
+    let columns = [
+        ("date_column", Some(mapping.date_column)),
+        ("description_column", Some(mapping.description_column)),
+        ("amount_column", mapping.amount_column),
+        ("debit_column", mapping.debit_column),
+        ("credit_column", mapping.credit_column),
+    ];
+    for (field, column) in columns {
+        if column.is_some_and(|column| column > MAX_COLUMN) {
+            return invalid(field, "Column number is too large");
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the user's profiles, or just the one with `id`.
+async fn fetch_profiles(pool: &PgPool, user: &User, id: Option<i32>) -> Result<Vec<CsvProfile>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, name, has_header, delimiter, date_column, date_format, description_column,
+            amount_column, debit_column, credit_column, decimal_separator, negate,
+            default_category_id, created_at
+        FROM csv_profiles
+        WHERE user_id = $1
+        AND ($2::int IS NULL OR id = $2)
+        ORDER BY name ASC
+        "#,
+        user.id,
+        id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| CsvProfile {
+        id: row.id,
+        name: row.name,
+        mapping: CsvMapping {
+            has_header: row.has_header,
+            delimiter: row.delimiter.chars().next().unwrap_or(','),
+            date_column: row.date_column as usize,
+            date_format: row.date_format,
+            description_column: row.description_column as usize,
+            amount_column: row.amount_column.map(|column| column as usize),
+            debit_column: row.debit_column.map(|column| column as usize),
+            credit_column: row.credit_column.map(|column| column as usize),
+            decimal_separator: row.decimal_separator.chars().next().unwrap_or('.'),
+            negate: row.negate,
+        },
+        default_category_id: row.default_category_id,
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(rows)
+}
+
+async fn fetch_profile(pool: &PgPool, user: &User, id: i32) -> Result<CsvProfile, AppError> {
+    fetch_profiles(pool, user, Some(id))
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::not_found("CSV profile not found"))
+}
+
+async fn list_profiles(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(fetch_profiles(&pool, &user, None).await?))
+}
+
+async fn get_profile(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(fetch_profile(&pool, &user, id).await?))
+}
+
+async fn create_profile(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewCsvProfile>,
+) -> Result<impl IntoResponse, AppError> {
+    validate_mapping(&payload.mapping)?;
+    let mapping = &payload.mapping;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO csv_profiles (
+            user_id, name, has_header, delimiter, date_column, date_format, description_column,
+            amount_column, debit_column, credit_column, decimal_separator, negate, default_category_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        RETURNING id
+        "#,
+        user.id,
+        payload.name.trim(),
+        mapping.has_header,
+        mapping.delimiter.to_string(),
+        mapping.date_column as i32,
+        mapping.date_format,
+        mapping.description_column as i32,
+        mapping.amount_column.map(|column| column as i32),
+        mapping.debit_column.map(|column| column as i32),
+        mapping.credit_column.map(|column| column as i32),
+        mapping.decimal_separator.to_string(),
+        mapping.negate,
+        payload.default_category_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(fetch_profile(&pool, &user, id).await?)))
+}
+
+async fn update_profile(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewCsvProfile>,
+) -> Result<impl IntoResponse, AppError> {
+    validate_mapping(&payload.mapping)?;
+    let mapping = &payload.mapping;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE csv_profiles
+        SET name = $3, has_header = $4, delimiter = $5, date_column = $6, date_format = $7,
+            description_column = $8, amount_column = $9, debit_column = $10, credit_column = $11,
+            decimal_separator = $12, negate = $13, default_category_id = $14
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user.id,
+        payload.name.trim(),
+        mapping.has_header,
+        mapping.delimiter.to_string(),
+        mapping.date_column as i32,
+        mapping.date_format,
+        mapping.description_column as i32,
+        mapping.amount_column.map(|column| column as i32),
+        mapping.debit_column.map(|column| column as i32),
+        mapping.credit_column.map(|column| column as i32),
+        mapping.decimal_separator.to_string(),
+        mapping.negate,
+        payload.default_category_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("CSV profile not found"));
+    }
+
+    Ok(Json(fetch_profile(&pool, &user, id).await?))
+}
+
+async fn delete_profile(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query!(
+        r#"DELETE FROM csv_profiles WHERE id = $1 AND user_id = $2"#,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("CSV profile not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Imports a bank's CSV export using a saved profile. With `dry_run`, returns the parsed rows and
+/// any problems without saving anything. Otherwise the file is imported only if every row can be read,
+/// into `account_id` and its currency when given, or the user's base currency when not.
+async fn import_csv(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(query): Query<CsvImportQuery>,
+    body: Bytes,
+) -> Result<axum::response::Response, AppError> {
+    let profile = fetch_profile(&pool, &user, query.profile_id).await?;
+    let preview = parse_csv(&decode(&body), &profile.mapping);
+
+    if query.dry_run {
+        return Ok(Json(preview).into_response());
+    }
+
+    if !preview.errors.is_empty() {
+        let error = AppError::validation(format!("{} rows could not be read; nothing was imported", preview.errors.len()));
+        let error = preview
+            .errors
+            .iter()
+            .fold(error, |error, row| error.with_field("rows", format!("Line {}: {}", row.line, row.message)));
+        return Err(error);
+    }
+
+    let category_id = profile.default_category_id.ok_or_else(|| {
+        AppError::validation("The profile needs a default category to import into")
+            .with_field("default_category_id", "Required")
+    })?;
+
+    let currency = match query.account_id {
+        Some(account_id) => {
+            let account = sqlx::query!(
+                r#"SELECT currency FROM accounts WHERE id = $1 AND user_id = $2"#,
+                account_id,
+                user.id
+            )
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::validation("Unknown account").with_field("account_id", "Account not found"))?;
+
+            Currency::from(account.currency)
+        }
+        None => user.base_currency.clone(),
+    };
+
+    let count = preview.rows.len();
+    let mut kinds = Vec::with_capacity(count);
+    let mut descriptions = Vec::with_capacity(count);
+    let mut amounts: Vec<BigDecimal> = Vec::with_capacity(count);
+    let mut dates = Vec::with_capacity(count);
+
+    for row in preview.rows {
+        kinds.push(row.kind.as_str().to_string());
+        descriptions.push(row.description);
+        amounts.push(row.amount.as_decimal().clone());
+        dates.push(convert_chrono_to_time(row.created_at));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO transactions (user_id, category_id, account_id, currency, kind, description, amount, created_at)
+        SELECT $1, $2, $3, $4, * FROM UNNEST($5::text[], $6::text[], $7::numeric[], $8::timestamptz[])
+        "#,
+        user.id,
+        category_id,
+        query.account_id,
+        currency.as_str(),
+        &kinds,
+        &descriptions,
+        &amounts,
+        &dates
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    evaluate_spending_limits(&mut *tx, user.id, &dates).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(CsvImportResult { imported: count }).into_response())
+}
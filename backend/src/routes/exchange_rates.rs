@@ -0,0 +1,118 @@
+use axum::{extract::{DefaultBodyLimit, Query}, http::{header::CONTENT_TYPE, HeaderMap}, response::IntoResponse, routing::{get, post}, Extension, Json, Router};
+use chrono::{NaiveTime, TimeZone, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    exchange::{find_rate, parse_ecb_xml, parse_rates_csv, upsert_rates},
+    middleware::AuthSession,
+    models::exchange_rate::{ExchangeRate, ExchangeRateFilter, ExchangeRateImportResult, ExchangeRateLookup, ExchangeRateQuery},
+    time_conversion::convert_date_to_chrono
+};
+
+/// Full ECB history files are tens of megabytes.
+const MAX_RATE_FILE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Lookup results are rounded to the precision rates are stored with.
+const LOOKUP_RATE_SCALE: i64 = 10;
+
+pub fn routes() -> Router {
+    Router::new().route("/exchange-rates", get(list_rates).put(put_rates))
+        .route("/exchange-rates/import", post(import_rates).layer(DefaultBodyLimit::max(MAX_RATE_FILE_SIZE)))
+        .route("/exchange-rates/lookup", get(lookup_rate))
+}
+
+async fn list_rates(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(filter): Query<ExchangeRateFilter>,
+) -> Result<impl IntoResponse, AppError> {
+    let rows: Vec<ExchangeRate> = sqlx::query!(
+        r#"
+        SELECT rate_date, from_currency, to_currency, rate
+        FROM exchange_rates
+        WHERE user_id = $1
+        AND ($2::text IS NULL OR from_currency = $2)
+        AND ($3::text IS NULL OR to_currency = $3)
+        ORDER BY rate_date DESC, from_currency, to_currency
+        "#,
+        user.id,
+        filter.from.as_ref().map(|currency| currency.as_str()),
+        filter.to.as_ref().map(|currency| currency.as_str())
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| ExchangeRate {
+        date: convert_date_to_chrono(row.rate_date),
+        from_currency: row.from_currency.into(),
+        to_currency: row.to_currency.into(),
+        rate: row.rate.into(),
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+/// Manually inserts or replaces rates.
+async fn put_rates(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<Vec<ExchangeRate>>,
+) -> Result<impl IntoResponse, AppError> {
+    if payload.iter().any(|rate| rate.from_currency == rate.to_currency) {
+        return Err(AppError::bad_request("Cannot set a rate from a currency to itself"));
+    }
+
+    let upserted = upsert_rates(&pool, user.id, payload).await?;
+
+    Ok(Json(ExchangeRateImportResult { upserted }))
+}
+
+/// Bulk-loads rates from a CSV file or an ECB `eurofxref` XML file, chosen by `Content-Type`.
+async fn import_rates(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    headers: HeaderMap,
+    body: String,
+) -> Result<impl IntoResponse, AppError> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let is_xml = content_type.contains("xml") || body.trim_start().starts_with('<');
+
+    let parsed = if is_xml { parse_ecb_xml(&body) } else { parse_rates_csv(&body) };
+
+    let rates = match parsed {
+        Ok(rates) => rates,
+        Err(e) => return Err(AppError::bad_request(e)),
+    };
+
+    if rates.iter().any(|rate| rate.from_currency == rate.to_currency) {
+        return Err(AppError::bad_request("Cannot set a rate from a currency to itself"));
+    }
+
+    let upserted = upsert_rates(&pool, user.id, rates).await?;
+
+    Ok(Json(ExchangeRateImportResult { upserted }))
+}
+
+async fn lookup_rate(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(query): Query<ExchangeRateQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    // Rates are daily, so any time on the requested day finds the same rate
+    let at = Utc.from_utc_datetime(&query.date.and_time(NaiveTime::MIN));
+
+    let rate = find_rate(&pool, user.id, &query.from, &query.to, at).await?;
+
+    Ok(Json(ExchangeRateLookup {
+        date: query.date,
+        from_currency: query.from,
+        to_currency: query.to,
+        rate: rate.map(|rate| rate.round(LOOKUP_RATE_SCALE).into()),
+    }))
+}
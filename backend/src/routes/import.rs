@@ -1,15 +1,23 @@
-use axum::{http::StatusCode, routing::post, Extension, Json, Router};
+use axum::{body::Bytes, extract::{DefaultBodyLimit, Query}, http::StatusCode, routing::post, Extension, Json, Router};
 use crate::{
+    alerts::evaluate_spending_limits,
+    currency::Currency,
+    encoding::decode,
+    error::AppError,
     middleware::AuthSession,
-    models::import_payload::{ImportCategory, ImportPayload},
+    models::{import_payload::{ImportCategory, ImportPayload, OfxImportQuery, OfxImportResult}, transaction::TransactionKind},
+    routes::{ofx, tags::set_transaction_tags},
     time_conversion::convert_chrono_to_time
 };
-use sqlx::PgPool;
-use bigdecimal::{BigDecimal, FromPrimitive};
+use sqlx::{types::BigDecimal, PgPool};
 use std::collections::{HashMap, VecDeque};
 
+/// Statements covering several years of a busy account.
+const MAX_OFX_FILE_SIZE: usize = 16 * 1024 * 1024;
+
 pub fn routes() -> Router {
     Router::new().route("/import", post(import_data))
+        .route("/import/ofx", post(import_ofx).layer(DefaultBodyLimit::max(MAX_OFX_FILE_SIZE)))
 }
 
 /// Validates and sorts categories topologically (parents before children).
@@ -75,20 +83,12 @@ pub async fn import_data(
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession,
     Json(payload): Json<ImportPayload>,
-) -> StatusCode {
+) -> Result<StatusCode, AppError> {
     // Validation (Fails fast before we ever touch the database)
-    let sorted_categories = match sort_categories_topologically(payload.categories) {
-        Ok(categories) => categories,
-        Err(e) => {
-            eprintln!("Import Validation Failed: {}", e);
-            return StatusCode::BAD_REQUEST;
-        }
-    };
+    let sorted_categories = sort_categories_topologically(payload.categories)
+        .map_err(|e| AppError::validation(e).with_field("categories", e))?;
 
-    let mut tx = match pool.begin().await {
-        Ok(t) => t,
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
-    };
+    let mut tx = pool.begin().await?;
 
     let mut category_map: HashMap<String, i32> = HashMap::new();
 
@@ -106,8 +106,7 @@ pub async fn import_data(
             convert_chrono_to_time(cat.created_at),
         )
         .fetch_one(&mut *tx)
-        .await
-        .expect("Failed to insert category");
+        .await?;
 
         let new_id = rec.id;
 
@@ -128,8 +127,7 @@ pub async fn import_data(
                     user.id
                 )
                 .execute(&mut *tx)
-                .await
-                .expect("Failed to insert hierarchy link");
+                .await?;
             }
         } else {
             // Ensure no hierarchy link exists if the import explicitly sets it to null
@@ -138,44 +136,138 @@ pub async fn import_data(
                 new_id
             )
             .execute(&mut *tx)
-            .await
-            .expect("Failed to delete hierarchy link");
+            .await?;
         }
     }
 
     // Insert Transactions
+    let mut dates = Vec::with_capacity(payload.transactions.len());
     for tx_item in payload.transactions {
-        let category_id = match category_map.get(&tx_item.category_name) {
-            Some(id) => *id,
-            None => {
-                return StatusCode::BAD_REQUEST;
-            }
+        let Some(&category_id) = category_map.get(&tx_item.category_name) else {
+            return Err(AppError::validation(format!("Unknown category \"{}\"", tx_item.category_name))
+                .with_field("transactions", "Every transaction must use one of the imported categories"));
         };
 
-        let amount = BigDecimal::from_f64(tx_item.amount).unwrap_or(BigDecimal::from(0));
+        let kind = tx_item.kind.unwrap_or_else(|| TransactionKind::from_amount(&tx_item.amount));
+        if kind == TransactionKind::Transfer || !kind.accepts(&tx_item.amount) {
+            return Err(AppError::validation(format!("Invalid amount for \"{}\"", tx_item.description))
+                .with_field("transactions", "Expenses must be negative and income positive, and transfers can't be imported"));
+        }
 
-        let _ = sqlx::query!(
+        let record = sqlx::query!(
             r#"
-            INSERT INTO transactions (user_id, category_id, description, amount, created_at)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO transactions (user_id, category_id, kind, description, amount, currency, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
             "#,
             user.id,
             category_id,
+            kind.as_str(),
             tx_item.description,
-            amount,
+            tx_item.amount.as_decimal(),
+            tx_item.currency.as_ref().unwrap_or(&user.base_currency).as_str(),
             convert_chrono_to_time(tx_item.created_at),
         )
-        .execute(&mut *tx)
-        .await
-        .expect("Failed to insert transaction");
+        .fetch_one(&mut *tx)
+        .await?;
+
+        dates.push(convert_chrono_to_time(tx_item.created_at));
+
+        if !tx_item.tags.is_empty() {
+            set_transaction_tags(&mut tx, user.id, record.id, &tx_item.tags).await?;
+        }
     }
 
+    evaluate_spending_limits(&mut *tx, user.id, &dates).await?;
+
     // Commit everything
-    if let Err(_) = tx.commit().await {
-        return StatusCode::INTERNAL_SERVER_ERROR;
+    tx.commit().await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Imports the transactions of an OFX or QFX statement into `category_id` and, optionally, `account_id`.
+/// Transactions whose `FITID` was already imported are skipped, so overlapping statements can be loaded.
+/// Like `import_data`, nothing is saved unless the whole file is valid.
+pub async fn import_ofx(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(query): Query<OfxImportQuery>,
+    body: Bytes,
+) -> Result<Json<OfxImportResult>, AppError> {
+    let transactions = ofx::parse_ofx(&decode(&body))
+        .map_err(|e| AppError::validation(format!("Could not read the statement: {}", e)).with_field("file", e))?;
+
+    // An account fixes the currency, and the statement has to agree with it
+    let account_currency = match query.account_id {
+        Some(account_id) => {
+            let account = sqlx::query!(
+                r#"SELECT currency FROM accounts WHERE id = $1 AND user_id = $2"#,
+                account_id,
+                user.id
+            )
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::validation("Unknown account").with_field("account_id", "Account not found"))?;
+
+            Some(Currency::from(account.currency))
+        }
+        None => None,
+    };
+
+    let count = transactions.len();
+    let mut external_ids = Vec::with_capacity(count);
+    let mut kinds = Vec::with_capacity(count);
+    let mut descriptions = Vec::with_capacity(count);
+    let mut amounts: Vec<BigDecimal> = Vec::with_capacity(count);
+    let mut currencies = Vec::with_capacity(count);
+    let mut dates = Vec::with_capacity(count);
+
+    for transaction in transactions {
+        let currency = match (&account_currency, transaction.currency) {
+            (Some(account_currency), Some(currency)) if currency != *account_currency => {
+                return Err(AppError::validation(format!("The statement is in {} but the account is in {}", currency, account_currency))
+                    .with_field("account_id", "Must match the statement's currency"));
+            }
+            (Some(account_currency), _) => account_currency.clone(),
+            (None, currency) => currency.unwrap_or_else(|| user.base_currency.clone()),
+        };
+
+        external_ids.push(transaction.external_id);
+        kinds.push(TransactionKind::from_amount(&transaction.amount).as_str().to_string());
+        descriptions.push(transaction.description);
+        amounts.push(transaction.amount.as_decimal().clone());
+        currencies.push(currency.as_str().to_string());
+        dates.push(convert_chrono_to_time(transaction.posted_at));
     }
 
-    StatusCode::OK
+    let mut tx = pool.begin().await?;
+
+    let imported = sqlx::query!(
+        r#"
+        INSERT INTO transactions (user_id, category_id, account_id, external_id, kind, description, amount, currency, created_at)
+        SELECT $1, $2, $3, * FROM UNNEST($4::text[], $5::text[], $6::text[], $7::numeric[], $8::text[], $9::timestamptz[])
+        ON CONFLICT (user_id, external_id) DO NOTHING
+        "#,
+        user.id,
+        query.category_id,
+        query.account_id,
+        &external_ids,
+        &kinds,
+        &descriptions,
+        &amounts,
+        &currencies,
+        &dates
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    evaluate_spending_limits(&mut *tx, user.id, &dates).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(OfxImportResult { imported, skipped: count as u64 - imported }))
 }
 
 
@@ -1,8 +1,8 @@
 use axum::{http::StatusCode, response::IntoResponse, routing::post, Extension, Json, Router};
-use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use axum_extra::extract::cookie::CookieJar;
 use sqlx::PgPool;
 
-use crate::{models::user::NewUser, passwords::verify_password};
+use crate::{error::AppError, middleware::ClientInfo, models::user::NewUser, passwords::verify_password, sessions::{create_session, session_cookie}};
 
 pub fn routes() -> Router {
     Router::new().route("/login", post(login))
@@ -11,8 +11,9 @@ pub fn routes() -> Router {
 pub async fn login(
     Extension(pool): Extension<PgPool>,
     jar: CookieJar,
+    client: ClientInfo,
     Json(payload): Json<NewUser>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, AppError> {
     let record = sqlx::query!(
         r#"
         SELECT id, username, password_hash, created_at
@@ -22,28 +23,19 @@ pub async fn login(
         payload.username
     )
     .fetch_optional(&pool)
-    .await
-    .expect("Failed to fetch user");
+    .await?;
 
-    let user = match record {
-        Some(user) => user,
-        _ => return (StatusCode::UNAUTHORIZED, "Invalid credentials").into_response(),
+    let Some(user) = record else {
+        return Err(AppError::unauthorized("Invalid credentials"));
     };
 
-    let password_is_valid = match verify_password(&user.password_hash, &payload.password) {
-        Ok(true) => true,
-        _ => false,
-    };
+    let password_is_valid = matches!(verify_password(&user.password_hash, &payload.password), Ok(true));
 
     if !password_is_valid {
-        return (StatusCode::UNAUTHORIZED, "Invalid credentials").into_response()
+        return Err(AppError::unauthorized("Invalid credentials"));
     }
 
-    let cookie = Cookie::build(("session", user.id.to_string()))
-        .path("/")
-        .http_only(true)
-        .secure(false)
-        .same_site(SameSite::Lax);
+    let token = create_session(&pool, user.id, &client).await?;
 
-    (jar.add(cookie), StatusCode::OK).into_response()
+    Ok((jar.add(session_cookie(token)), StatusCode::OK))
 }
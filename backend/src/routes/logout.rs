@@ -1,17 +1,20 @@
-use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
-use axum_extra::extract::cookie::{Cookie, CookieJar};
-use tower_cookies::cookie::time;
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Extension, Router};
+use axum_extra::extract::cookie::CookieJar;
+use sqlx::PgPool;
+
+use crate::{error::AppError, sessions::{cleared_session_cookie, revoke_session, SESSION_COOKIE}};
 
 pub fn routes() -> Router {
     Router::new().route("/logout", post(logout))
 }
 
-pub async fn logout(jar: CookieJar) -> impl IntoResponse {
-    let cleared = Cookie::build(("session", ""))
-        .path("/")
-        .max_age(time::Duration::seconds(0))
-        .http_only(true)
-        .secure(false);
+pub async fn logout(
+    Extension(pool): Extension<PgPool>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(session_cookie) = jar.get(SESSION_COOKIE) {
+        revoke_session(&pool, session_cookie.value()).await?;
+    }
 
-    (jar.remove(cleared), StatusCode::OK).into_response()
+    Ok((jar.remove(cleared_session_cookie()), StatusCode::OK))
 }
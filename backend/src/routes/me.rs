@@ -1,13 +1,24 @@
-use axum::Json;
+use axum::{Extension, Json};
+use axum::extract::Path;
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 
-use axum::{Router, routing::get};
+use axum::{Router, routing::{delete, get}};
+use axum_extra::extract::cookie::CookieJar;
+use sqlx::PgPool;
+use uuid::Uuid;
 
-use crate::middleware::AuthSession;
-use crate::models::user::User;
+use crate::error::AppError;
+use crate::middleware::{AuthMethod, AuthSession};
+use crate::models::{session::Session, user::{UpdateUser, User}};
+use crate::sessions::SESSION_COOKIE;
+use crate::time_conversion::convert_time_to_chrono;
+use crate::tokens::hash_token;
 
 pub fn routes() -> Router {
-    Router::new().route("/me", get(login_check))
+    Router::new().route("/me", get(login_check).patch(update_me))
+        .route("/me/sessions", get(list_sessions).delete(revoke_other_sessions))
+        .route("/me/sessions/{id}", delete(revoke_session))
 }
 
 async fn login_check(AuthSession(user): AuthSession) -> impl IntoResponse {
@@ -15,8 +26,128 @@ async fn login_check(AuthSession(user): AuthSession) -> impl IntoResponse {
         id: user.id,
         username: user.username,
         password_hash: user.password_hash,
+        base_currency: user.base_currency,
         created_at: user.created_at,
     };
 
     Json(user)
 }
+
+async fn update_me(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<UpdateUser>,
+) -> Result<impl IntoResponse, AppError> {
+    let base_currency = payload.base_currency.unwrap_or(user.base_currency);
+
+    let record = sqlx::query!(
+        r#"
+        UPDATE users SET base_currency = $1 WHERE id = $2
+        RETURNING id, username, password_hash, base_currency, created_at
+        "#,
+        base_currency.as_str(),
+        user.id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let updated = User {
+        id: record.id,
+        username: record.username,
+        password_hash: record.password_hash,
+        base_currency: record.base_currency.into(),
+        created_at: convert_time_to_chrono(record.created_at),
+    };
+
+    Ok(Json(updated))
+}
+
+/// Hash of the session cookie on this request, used to tell the caller's own session apart from the others.
+fn current_token_hash(jar: &CookieJar) -> String {
+    jar.get(SESSION_COOKIE)
+        .map(|cookie| hash_token(cookie.value()))
+        .unwrap_or_default()
+}
+
+async fn list_sessions(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AppError> {
+    let rows: Vec<Session> = sqlx::query!(
+        r#"
+        SELECT id, user_agent, ip_address, created_at, last_seen, expires_at,
+            token_hash = $2 AS "current!"
+        FROM sessions
+        WHERE user_id = $1 AND expires_at > now()
+        ORDER BY last_seen DESC
+        "#,
+        user.id,
+        current_token_hash(&jar)
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| Session {
+        id: row.id,
+        user_agent: row.user_agent,
+        ip_address: row.ip_address,
+        created_at: convert_time_to_chrono(row.created_at),
+        last_seen: convert_time_to_chrono(row.last_seen),
+        expires_at: convert_time_to_chrono(row.expires_at),
+        current: row.current,
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+async fn revoke_session(
+    Path(id): Path<Uuid>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Extension(auth_method): Extension<AuthMethod>,
+) -> Result<StatusCode, AppError> {
+    auth_method.require_session("API tokens cannot revoke sessions")?;
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM sessions
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Session not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Logs out every device except the one making this request.
+async fn revoke_other_sessions(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Extension(auth_method): Extension<AuthMethod>,
+    jar: CookieJar,
+) -> Result<StatusCode, AppError> {
+    // An API token has no session of its own to keep, so every session would match below
+    auth_method.require_session("Only a logged-in session can revoke the other sessions")?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM sessions
+        WHERE user_id = $1 AND token_hash <> $2
+        "#,
+        user.id,
+        current_token_hash(&jar)
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -5,3 +5,17 @@ pub mod login;
 pub mod logout;
 pub mod categories;
 pub mod import;
+pub mod ofx;
+pub mod api_tokens;
+pub mod exchange_rates;
+pub mod accounts;
+pub mod transfers;
+pub mod reports;
+pub mod budgets;
+pub mod spending_limits;
+pub mod notifications;
+pub mod recurring_transactions;
+pub mod search;
+pub mod tags;
+pub mod attachments;
+pub mod csv_import;
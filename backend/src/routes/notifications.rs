@@ -0,0 +1,111 @@
+use axum::{extract::{Path, Query}, http::StatusCode, response::IntoResponse, routing::{get, patch, post}, Extension, Json, Router};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    middleware::AuthSession,
+    models::notification::{Notification, NotificationFilter, NotificationUpdate},
+    time_conversion::{convert_date_to_chrono, convert_time_to_chrono, format_month}
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/notifications", get(list_notifications))
+        .route("/notifications/read", post(mark_all_read))
+        .route("/notifications/{id}", patch(update_notification).delete(delete_notification))
+}
+
+async fn list_notifications(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(filter): Query<NotificationFilter>,
+) -> Result<impl IntoResponse, AppError> {
+    let notifications: Vec<Notification> = sqlx::query!(
+        r#"
+        SELECT id, category_id, period, threshold, spent, limit_amount, message, read_at, created_at
+        FROM notifications
+        WHERE user_id = $1
+        AND (NOT $2 OR read_at IS NULL)
+        ORDER BY created_at DESC, id DESC
+        "#,
+        user.id,
+        filter.unread
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| Notification {
+        id: row.id,
+        category_id: row.category_id,
+        month: format_month(convert_date_to_chrono(row.period)),
+        threshold: row.threshold,
+        spent: row.spent.into(),
+        limit: row.limit_amount.into(),
+        message: row.message,
+        read: row.read_at.is_some(),
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(notifications))
+}
+
+/// Marks a single notification as read or unread.
+async fn update_notification(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NotificationUpdate>,
+) -> Result<impl IntoResponse, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET read_at = CASE WHEN $1 THEN COALESCE(read_at, now()) ELSE NULL END
+        WHERE id = $2 AND user_id = $3
+        "#,
+        payload.read,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Notification not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn mark_all_read(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    sqlx::query!(
+        r#"UPDATE notifications SET read_at = now() WHERE user_id = $1 AND read_at IS NULL"#,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_notification(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let result = sqlx::query!(
+        r#"DELETE FROM notifications WHERE id = $1 AND user_id = $2"#,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Notification not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,296 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+
+use crate::{currency::Currency, money::Money};
+
+/// A `STMTTRN` record from an OFX or QFX statement.
+#[derive(Debug)]
+pub struct OfxTransaction {
+    /// The bank account and `FITID`, unique for every transaction the bank reports
+    pub external_id: String,
+    pub posted_at: DateTime<Utc>,
+    pub amount: Money,
+    pub description: String,
+    /// `None` when the statement doesn't say
+    pub currency: Option<Currency>,
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let replacement = match entity {
+            Some("amp") => Some('&'),
+            Some("lt") => Some('<'),
+            Some("gt") => Some('>'),
+            Some("quot") => Some('"'),
+            Some("apos") => Some('\''),
+            Some(code) if code.starts_with("#x") => u32::from_str_radix(&code[2..], 16).ok().and_then(char::from_u32),
+            Some(code) if code.starts_with('#') => code[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+
+        match (replacement, entity) {
+            (Some(c), Some(entity)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Reads an OFX date: `YYYYMMDD`, optionally followed by `HHMMSS`, milliseconds and a `[-5:EST]` offset in hours.
+/// Dates without an offset are in UTC, as the spec says.
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let (datetime, offset) = match value.split_once('[') {
+        Some((datetime, zone)) => (datetime, Some(zone.trim_end_matches(']'))),
+        None => (value, None),
+    };
+    let digits = datetime.split('.').next()?.trim();
+
+    if digits.len() < 8 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let field = |range: std::ops::Range<usize>| digits.get(range).map_or(Some(0), |part| part.parse().ok());
+    let date = NaiveDate::from_ymd_opt(field(0..4)? as i32, field(4..6)?, field(6..8)?)?;
+    let time = NaiveTime::from_hms_opt(field(8..10)?, field(10..12)?, field(12..14)?)?;
+
+    let offset_hours: f64 = match offset {
+        Some(zone) => zone.split(':').next()?.trim().parse().ok()?,
+        None => 0.0,
+    };
+    let offset = Duration::seconds((offset_hours * 3600.0).round() as i64);
+
+    Some(date.and_time(time).and_utc() - offset)
+}
+
+fn parse_amount(value: &str) -> Option<Money> {
+    // Some European banks write a decimal comma
+    let value = if value.contains('.') { value.to_string() } else { value.replace(',', ".") };
+    Money::from_str(value.trim_start_matches('+')).ok()
+}
+
+/// Fields of the `STMTTRN` being read.
+#[derive(Default)]
+struct Record {
+    fitid: Option<String>,
+    posted: Option<String>,
+    amount: Option<String>,
+    name: Option<String>,
+    memo: Option<String>,
+    kind: Option<String>,
+    currency: Option<String>,
+}
+
+impl Record {
+    fn finish(self, number: usize, account: &str, statement_currency: Option<&Currency>) -> Result<OfxTransaction, String> {
+        let missing = |field| format!("Transaction {} has no {}", number, field);
+
+        let fitid = self.fitid.ok_or_else(|| missing("FITID"))?;
+        let posted = self.posted.ok_or_else(|| missing("DTPOSTED"))?;
+        let amount = self.amount.ok_or_else(|| missing("TRNAMT"))?;
+
+        let posted_at = parse_date(&posted)
+            .ok_or_else(|| format!("Transaction {} has an invalid DTPOSTED \"{}\"", fitid, posted))?;
+        let amount = parse_amount(&amount)
+            .ok_or_else(|| format!("Transaction {} has an invalid TRNAMT \"{}\"", fitid, amount))?;
+
+        let currency = match self.currency {
+            Some(code) => Some(code.parse().map_err(|_| format!("Transaction {} has an invalid currency \"{}\"", fitid, code))?),
+            None => statement_currency.cloned(),
+        };
+
+        let description = match (self.name, self.memo) {
+            (Some(name), Some(memo)) if memo != name => format!("{} - {}", name, memo),
+            (Some(name), _) => name,
+            (None, Some(memo)) => memo,
+            (None, None) => self.kind.unwrap_or_else(|| "Bank transaction".to_string()),
+        };
+
+        Ok(OfxTransaction {
+            external_id: format!("ofx:{}:{}", account, fitid),
+            posted_at,
+            amount,
+            description,
+            currency,
+        })
+    }
+}
+
+/// Extracts the `STMTTRN` records of every bank and credit card statement in an OFX file.
+///
+/// Handles both OFX 1.x (SGML, where elements holding a value have no end tag) and OFX 2.x (XML)
+/// by treating any element followed by text as a value and everything else as an aggregate.
+pub fn parse_ofx(input: &str) -> Result<Vec<OfxTransaction>, String> {
+    let Some(start) = input.find("<OFX>") else {
+        return Err("Not an OFX file".to_string());
+    };
+
+    let mut transactions = Vec::new();
+    // Open aggregates, innermost last
+    let mut open: Vec<&str> = Vec::new();
+    let mut account = String::new();
+    let mut statement_currency: Option<Currency> = None;
+    let mut record: Option<Record> = None;
+
+    for element in input[start..].split('<').skip(1) {
+        let Some((tag, text)) = element.split_once('>') else {
+            return Err(format!("Unclosed tag <{}", element.trim()));
+        };
+
+        // XML declarations, processing instructions and comments
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            // End tags of values (XML only) don't match an open aggregate and are skipped
+            if let Some(position) = open.iter().rposition(|open| *open == name.trim()) {
+                open.truncate(position);
+
+                if name.trim() == "STMTTRN" && let Some(finished) = record.take() {
+                    let number = transactions.len() + 1;
+                    transactions.push(finished.finish(number, &account, statement_currency.as_ref())?);
+                }
+            }
+            continue;
+        }
+
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        let value = decode_entities(text.trim());
+
+        if value.is_empty() {
+            if name == "STMTTRN" {
+                record = Some(Record::default());
+            }
+            open.push(name);
+            continue;
+        }
+
+        let parent = open.last().copied().unwrap_or_default();
+        match (record.as_mut(), parent, name) {
+            (Some(record), "STMTTRN", "FITID") => record.fitid = Some(value),
+            (Some(record), "STMTTRN", "DTPOSTED") => record.posted = Some(value),
+            (Some(record), "STMTTRN", "TRNAMT") => record.amount = Some(value),
+            (Some(record), "STMTTRN", "NAME") => record.name = Some(value),
+            (Some(record), "STMTTRN", "MEMO") => record.memo = Some(value),
+            (Some(record), "STMTTRN", "TRNTYPE") => record.kind = Some(value),
+            // TRNAMT is in this currency rather than the statement's
+            (Some(record), "CURRENCY", "CURSYM") => record.currency = Some(value),
+            (None, "BANKACCTFROM" | "CCACCTFROM", "ACCTID") => account = value,
+            (None, _, "CURDEF") => {
+                statement_currency = Some(value.parse().map_err(|_| format!("Invalid statement currency \"{}\"", value))?);
+            }
+            _ => {}
+        }
+    }
+
+    if record.is_some() {
+        return Err("The file ends in the middle of a transaction".to_string());
+    }
+
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SGML: &str = "OFXHEADER:100\r\nDATA:OFXSGML\r\nVERSION:102\r\nCHARSET:1252\r\n\r\n\
+<OFX><SIGNONMSGSRSV1><SONRS><STATUS><CODE>0<SEVERITY>INFO</STATUS><DTSERVER>20261005</SONRS></SIGNONMSGSRSV1>
+<BANKMSGSRSV1><STMTTRNRS><TRNUID>1<STMTRS><CURDEF>CAD
+<BANKACCTFROM><BANKID>004<ACCTID>12345<ACCTTYPE>CHECKING</BANKACCTFROM>
+<BANKTRANLIST><DTSTART>20261001<DTEND>20261005
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20261001120000.000[-5:EST]<TRNAMT>-4.50<FITID>A1<NAME>TIM HORTONS &amp; CO<MEMO>
+</STMTTRN>
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20261002<TRNAMT>2000.00<FITID>A2<NAME>PAYROLL<MEMO>OCTOBER</STMTTRN>
+</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<?OFX OFXHEADER="200" VERSION="220" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+<OFX>
+  <CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS>
+    <CURDEF>CAD</CURDEF>
+    <CCACCTFROM><ACCTID>4500999</ACCTID></CCACCTFROM>
+    <BANKTRANLIST>
+      <STMTTRN>
+        <TRNTYPE>DEBIT</TRNTYPE>
+        <DTPOSTED>20261003</DTPOSTED>
+        <TRNAMT>-25.00</TRNAMT>
+        <FITID>X9</FITID>
+        <PAYEE><NAME>Payee name</NAME></PAYEE>
+        <NAME>AMAZON.COM</NAME>
+        <CURRENCY><CURRATE>1.37</CURRATE><CURSYM>USD</CURSYM></CURRENCY>
+      </STMTTRN>
+    </BANKTRANLIST>
+  </CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>
+</OFX>"#;
+
+    #[test]
+    fn test_sgml_statement() {
+        let transactions = parse_ofx(SGML).unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].external_id, "ofx:12345:A1");
+        assert_eq!(transactions[0].description, "TIM HORTONS & CO");
+        assert_eq!(transactions[0].amount.to_string(), "-4.5");
+        assert_eq!(transactions[0].posted_at.to_rfc3339(), "2026-10-01T17:00:00+00:00");
+        assert_eq!(transactions[0].currency.as_ref().map(Currency::as_str), Some("CAD"));
+        assert_eq!(transactions[1].description, "PAYROLL - OCTOBER");
+        assert_eq!(transactions[1].posted_at.to_rfc3339(), "2026-10-02T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_xml_statement() {
+        let transactions = parse_ofx(XML).unwrap();
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].external_id, "ofx:4500999:X9");
+        assert_eq!(transactions[0].description, "AMAZON.COM");
+        assert_eq!(transactions[0].currency.as_ref().map(Currency::as_str), Some("USD"));
+    }
+
+    #[test]
+    fn test_dates() {
+        let date = |value| parse_date(value).map(|date| date.to_rfc3339());
+
+        assert_eq!(date("20261001"), Some("2026-10-01T00:00:00+00:00".to_string()));
+        assert_eq!(date("20261001083000[+5.5:IST]"), Some("2026-10-01T03:00:00+00:00".to_string()));
+        assert_eq!(date("20261301"), None);
+        assert_eq!(date("2026"), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse_ofx("date,amount\n").is_err());
+
+        let missing_fitid = "<OFX><STMTTRN><DTPOSTED>20261001<TRNAMT>1.00</STMTTRN></OFX>";
+        assert_eq!(parse_ofx(missing_fitid).unwrap_err(), "Transaction 1 has no FITID");
+
+        let truncated = "<OFX><STMTTRN><DTPOSTED>20261001<TRNAMT>1.00<FITID>1";
+        assert!(parse_ofx(truncated).is_err());
+    }
+
+    #[test]
+    fn test_decodes_entities() {
+        assert_eq!(decode_entities("A &lt;B&gt; &#233; &#xE9; &bogus; &"), "A <B> é é &bogus; &");
+    }
+}
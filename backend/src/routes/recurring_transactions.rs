@@ -0,0 +1,287 @@
+use axum::{extract::{Path, Query}, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router};
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    currency::Currency,
+    middleware::AuthSession,
+    models::{recurring_transaction::{Frequency, NewRecurringTransaction, PreviewQuery, RecurringTransaction}, transaction::TransactionKind, user::User},
+    recurring::{materialize_template, Schedule},
+    routes::transactions::{resolve_currency, resolve_kind},
+    time_conversion::{convert_chrono_to_date, convert_date_to_chrono, convert_time_to_chrono}
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/recurring-transactions", get(list_recurring).post(create_recurring))
+        .route("/recurring-transactions/{id}", get(get_recurring).put(update_recurring).delete(delete_recurring))
+        .route("/recurring-transactions/{id}/preview", get(preview_recurring))
+}
+
+const DEFAULT_PREVIEW_COUNT: usize = 10;
+const MAX_PREVIEW_COUNT: usize = 366;
+
+async fn list_recurring(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let rows: Vec<RecurringTransaction> = sqlx::query!(
+        r#"
+        SELECT id, category_id, account_id, kind, description, amount, currency,
+            frequency, interval, start_date, end_date, created_at
+        FROM recurring_transactions
+        WHERE user_id = $1
+        ORDER BY description ASC, id ASC
+        "#,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| RecurringTransaction {
+        id: row.id,
+        category_id: row.category_id,
+        account_id: row.account_id,
+        kind: TransactionKind::from_db(&row.kind),
+        description: row.description,
+        amount: row.amount.into(),
+        currency: row.currency.into(),
+        frequency: Frequency::from_db(&row.frequency),
+        interval: row.interval,
+        start_date: convert_date_to_chrono(row.start_date),
+        end_date: row.end_date.map(convert_date_to_chrono),
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+/// Loads a single recurring transaction along with how far it has been materialized.
+async fn fetch_recurring(
+    pool: &PgPool,
+    user: &User,
+    id: i32,
+) -> Result<Option<(RecurringTransaction, Option<NaiveDate>)>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, category_id, account_id, kind, description, amount, currency,
+            frequency, interval, start_date, end_date, materialized_through, created_at
+        FROM recurring_transactions
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let recurring = RecurringTransaction {
+        id: row.id,
+        category_id: row.category_id,
+        account_id: row.account_id,
+        kind: TransactionKind::from_db(&row.kind),
+        description: row.description,
+        amount: row.amount.into(),
+        currency: row.currency.into(),
+        frequency: Frequency::from_db(&row.frequency),
+        interval: row.interval,
+        start_date: convert_date_to_chrono(row.start_date),
+        end_date: row.end_date.map(convert_date_to_chrono),
+        created_at: convert_time_to_chrono(row.created_at),
+    };
+
+    Ok(Some((recurring, row.materialized_through.map(convert_date_to_chrono))))
+}
+
+/// Checks the template like a regular transaction and its schedule makes sense.
+/// Returns the kind and currency each occurrence will be recorded with.
+async fn validate_recurring(
+    pool: &PgPool,
+    user: &User,
+    payload: &NewRecurringTransaction,
+) -> Result<(TransactionKind, Currency), AppError> {
+    if !(1..=1000).contains(&payload.interval) {
+        return Err(AppError::validation("Interval must be between 1 and 1000")
+            .with_field("interval", "Must be between 1 and 1000"));
+    }
+
+    if payload.end_date.is_some_and(|end| end < payload.start_date) {
+        return Err(AppError::validation("End date cannot be before the start date")
+            .with_field("end_date", "Cannot be before the start date"));
+    }
+
+    let template = payload.template();
+    let kind = resolve_kind(&template)?;
+    let currency = resolve_currency(pool, user, &template).await?;
+
+    let category = sqlx::query!(
+        r#"SELECT id FROM categories WHERE id = $1 AND user_id = $2"#,
+        payload.category_id,
+        user.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if category.is_none() {
+        return Err(AppError::not_found("Category not found"));
+    }
+
+    Ok((kind, currency))
+}
+
+async fn create_recurring(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewRecurringTransaction>,
+) -> Result<(StatusCode, Json<RecurringTransaction>), AppError> {
+    let (kind, currency) = validate_recurring(&pool, &user, &payload).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO recurring_transactions
+            (user_id, category_id, account_id, kind, description, amount, currency, frequency, interval, start_date, end_date)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id
+        "#,
+        user.id,
+        payload.category_id,
+        payload.account_id,
+        kind.as_str(),
+        payload.description,
+        payload.amount.as_decimal(),
+        currency.as_str(),
+        payload.frequency.as_str(),
+        payload.interval,
+        convert_chrono_to_date(payload.start_date),
+        payload.end_date.map(convert_chrono_to_date)
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Catch up on any occurrences that are already due
+    materialize_template(&mut tx, record.id, Utc::now().date_naive()).await?;
+
+    tx.commit().await?;
+
+    let (recurring, _) = fetch_recurring(&pool, &user, record.id)
+        .await?
+        .ok_or_else(AppError::internal)?;
+
+    Ok((StatusCode::CREATED, Json(recurring)))
+}
+
+async fn get_recurring(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    match fetch_recurring(&pool, &user, id).await? {
+        Some((recurring, _)) => Ok(Json(recurring)),
+        None => Err(AppError::not_found("Recurring transaction not found")),
+    }
+}
+
+/// Changes to the schedule only apply to occurrences that haven't been materialized yet.
+async fn update_recurring(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewRecurringTransaction>,
+) -> Result<Json<RecurringTransaction>, AppError> {
+    let (kind, currency) = validate_recurring(&pool, &user, &payload).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE recurring_transactions
+        SET category_id = $1, account_id = $2, kind = $3, description = $4, amount = $5, currency = $6,
+            frequency = $7, interval = $8, start_date = $9, end_date = $10
+        WHERE id = $11 AND user_id = $12
+        RETURNING id
+        "#,
+        payload.category_id,
+        payload.account_id,
+        kind.as_str(),
+        payload.description,
+        payload.amount.as_decimal(),
+        currency.as_str(),
+        payload.frequency.as_str(),
+        payload.interval,
+        convert_chrono_to_date(payload.start_date),
+        payload.end_date.map(convert_chrono_to_date),
+        id,
+        user.id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if row.is_none() {
+        return Err(AppError::not_found("Recurring transaction not found"));
+    }
+
+    materialize_template(&mut tx, id, Utc::now().date_naive()).await?;
+
+    tx.commit().await?;
+
+    let (recurring, _) = fetch_recurring(&pool, &user, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Recurring transaction not found"))?;
+
+    Ok(Json(recurring))
+}
+
+/// Stops the schedule. Transactions it already created are kept.
+async fn delete_recurring(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let result = sqlx::query!(
+        r#"DELETE FROM recurring_transactions WHERE id = $1 AND user_id = $2"#,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Recurring transaction not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The next occurrences that haven't been turned into transactions yet.
+async fn preview_recurring(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(query): Query<PreviewQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some((recurring, materialized_through)) = fetch_recurring(&pool, &user, id).await? else {
+        return Err(AppError::not_found("Recurring transaction not found"));
+    };
+
+    let schedule = Schedule {
+        frequency: recurring.frequency,
+        interval: recurring.interval as u32,
+        start: recurring.start_date,
+        end: recurring.end_date,
+    };
+
+    let count = query.count.unwrap_or(DEFAULT_PREVIEW_COUNT).min(MAX_PREVIEW_COUNT);
+    let upcoming: Vec<NaiveDate> = schedule
+        .occurrences_after(materialized_through)
+        .take(count)
+        .collect();
+
+    Ok(Json(upcoming))
+}
@@ -0,0 +1,184 @@
+use axum::{extract::Query, response::IntoResponse, routing::get, Extension, Json, Router};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    middleware::AuthSession,
+    models::report::{CashFlowMonth, CashFlowReport, CategorySummary, ReportRange, SummaryPeriod, SummaryQuery, SummaryReport, TagReport, TagSummary},
+    time_conversion::{convert_chrono_to_date, convert_date_to_chrono}
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/reports/cash-flow", get(cash_flow))
+        .route("/reports/summary", get(summary))
+        .route("/reports/tags", get(tags))
+}
+
+/// Income, expenses and net cash flow per month, in the user's base currency.
+/// Transfers between accounts are left out since they don't change the user's net worth.
+async fn cash_flow(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(range): Query<ReportRange>,
+) -> Result<impl IntoResponse, AppError> {
+    let months: Vec<CashFlowMonth> = sqlx::query!(
+        r#"
+        WITH converted AS (
+            SELECT t.kind, t.created_at AT TIME ZONE 'UTC' AS created_at, t.base_amount
+            FROM converted_transactions t
+            WHERE t.user_id = $1
+            AND t.kind <> 'transfer'
+            AND ($2::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date >= $2)
+            AND ($3::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date <= $3)
+        )
+        SELECT
+            to_char(date_trunc('month', created_at), 'YYYY-MM') AS "month!",
+            ROUND(COALESCE(SUM(base_amount) FILTER (WHERE kind = 'income'), 0), 4) AS "income!",
+            ROUND(COALESCE(-SUM(base_amount) FILTER (WHERE kind = 'expense'), 0), 4) AS "expenses!",
+            ROUND(COALESCE(SUM(base_amount), 0), 4) AS "net!",
+            COUNT(*) FILTER (WHERE base_amount IS NULL) AS "unconverted_count!"
+        FROM converted
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        user.id,
+        range.from.map(convert_chrono_to_date),
+        range.to.map(convert_chrono_to_date)
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| CashFlowMonth {
+        month: row.month,
+        income: row.income.into(),
+        expenses: row.expenses.into(),
+        net: row.net.into(),
+        unconverted_count: row.unconverted_count,
+    })
+    .collect();
+
+    Ok(Json(CashFlowReport {
+        currency: user.base_currency,
+        months,
+    }))
+}
+
+/// Totals per category for each week, month or year, in the user's base currency.
+/// Each category's total includes everything in its subcategories, so parents can be read on their own.
+async fn summary(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(query): Query<SummaryQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        WITH converted AS (
+            SELECT date_trunc($4::text, t.created_at AT TIME ZONE 'UTC')::date AS period, t.category_id, t.base_amount
+            FROM converted_categorized_transactions t
+            WHERE t.user_id = $1
+            AND t.kind <> 'transfer'
+            AND ($2::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date >= $2)
+            AND ($3::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date <= $3)
+        )
+        SELECT
+            cv.period AS "period!",
+            c.id AS category_id,
+            c.name,
+            ch.parent_id AS "parent_id?",
+            ROUND(COALESCE(SUM(cv.base_amount) FILTER (WHERE cv.category_id = c.id), 0), 4) AS "own_total!",
+            ROUND(COALESCE(SUM(cv.base_amount), 0), 4) AS "total!",
+            COUNT(*) FILTER (WHERE cv.base_amount IS NULL) AS "unconverted_count!"
+        FROM converted cv
+        JOIN category_ancestry($1) a ON a.category_id = cv.category_id
+        JOIN categories c ON c.id = a.ancestor_id
+        LEFT JOIN category_hierarchy ch ON ch.category_id = c.id
+        GROUP BY cv.period, c.id, c.name, ch.parent_id
+        ORDER BY cv.period ASC, c.name ASC
+        "#,
+        user.id,
+        query.from.map(convert_chrono_to_date),
+        query.to.map(convert_chrono_to_date),
+        query.granularity.as_str()
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    // Rows arrive sorted by period, so each new period starts a new group
+    let mut periods: Vec<SummaryPeriod> = Vec::new();
+    for row in rows {
+        let start = convert_date_to_chrono(row.period);
+        let category = CategorySummary {
+            category_id: row.category_id,
+            name: row.name,
+            parent_id: row.parent_id,
+            own_total: row.own_total.into(),
+            total: row.total.into(),
+            unconverted_count: row.unconverted_count,
+        };
+
+        match periods.last_mut() {
+            Some(period) if period.start == start => period.categories.push(category),
+            _ => periods.push(SummaryPeriod { start, categories: vec![category] }),
+        }
+    }
+
+    Ok(Json(SummaryReport {
+        currency: user.base_currency,
+        granularity: query.granularity,
+        periods,
+    }))
+}
+
+/// Income, expenses and net per tag, in the user's base currency. Tags that match nothing in the range are left out.
+async fn tags(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(range): Query<ReportRange>,
+) -> Result<impl IntoResponse, AppError> {
+    let tags: Vec<TagSummary> = sqlx::query!(
+        r#"
+        WITH converted AS (
+            SELECT tt.tag_id, t.kind, t.base_amount
+            FROM converted_transactions t
+            JOIN transaction_tags tt ON tt.transaction_id = t.transaction_id
+            WHERE t.user_id = $1
+            AND t.kind <> 'transfer'
+            AND ($2::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date >= $2)
+            AND ($3::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date <= $3)
+        )
+        SELECT
+            tg.id,
+            tg.name,
+            COUNT(*) AS "transaction_count!",
+            ROUND(COALESCE(SUM(cv.base_amount) FILTER (WHERE cv.kind = 'income'), 0), 4) AS "income!",
+            ROUND(COALESCE(-SUM(cv.base_amount) FILTER (WHERE cv.kind = 'expense'), 0), 4) AS "expenses!",
+            ROUND(COALESCE(SUM(cv.base_amount), 0), 4) AS "net!",
+            COUNT(*) FILTER (WHERE cv.base_amount IS NULL) AS "unconverted_count!"
+        FROM converted cv
+        JOIN tags tg ON tg.id = cv.tag_id
+        GROUP BY tg.id
+        ORDER BY tg.name
+        "#,
+        user.id,
+        range.from.map(convert_chrono_to_date),
+        range.to.map(convert_chrono_to_date)
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| TagSummary {
+        tag_id: row.id,
+        name: row.name,
+        transaction_count: row.transaction_count,
+        income: row.income.into(),
+        expenses: row.expenses.into(),
+        net: row.net.into(),
+        unconverted_count: row.unconverted_count,
+    })
+    .collect();
+
+    Ok(Json(TagReport {
+        currency: user.base_currency,
+        tags,
+    }))
+}
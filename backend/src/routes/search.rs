@@ -0,0 +1,147 @@
+use axum::{extract::Query, routing::get, Extension, Json, Router};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    middleware::AuthSession,
+    models::{category::Category, transaction::{SearchQuery, SearchResult, Transaction, TransactionKind}, transfer::TransferLink},
+    routes::transactions::attach_splits,
+    time_conversion::convert_time_to_chrono
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/transactions/search", get(search_transactions))
+}
+
+const DEFAULT_RESULT_COUNT: i64 = 50;
+const MAX_RESULT_COUNT: i64 = 200;
+
+// Postgres marks the matches in snippets with these, so they can't be confused with the description's own text
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+/// Escapes a snippet for HTML and swaps the match markers for `<mark>` tags.
+fn highlight_snippet(raw: &str) -> String {
+    let mut html = String::with_capacity(raw.len());
+
+    for c in raw.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+
+    html
+}
+
+/// Ranked full-text search over the user's transaction descriptions.
+async fn search_transactions(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>, AppError> {
+    let terms = query.q.trim();
+    if terms.is_empty() {
+        return Err(AppError::bad_request("Search query cannot be empty"));
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_RESULT_COUNT).clamp(1, MAX_RESULT_COUNT);
+    let headline_options = format!("StartSel={}, StopSel={}, HighlightAll=true", MATCH_START, MATCH_END);
+
+    let mut results: Vec<SearchResult> = sqlx::query!(
+        r#"
+        WITH search AS (
+            SELECT websearch_to_tsquery('english', $2) AS query
+        )
+        SELECT
+            t.id AS transaction_id, t.description, t.amount, t.currency, t.account_id, t.kind,
+            ROUND(t.amount * exchange_rate_on(t.user_id, t.currency, u.base_currency, t.created_at), 4) AS base_amount,
+            t.created_at AS transaction_created_at,
+            transaction_tag_names(t.id) AS "tags!",
+            c.id AS "category_id?", c.name AS "category_name?", c.created_at AS "category_created_at?",
+            ch.parent_id as "parent_id?",
+            tr.id as "transfer_id?",
+            fa.id as "from_account_id?", fa.name as "from_account_name?",
+            ta.id as "to_account_id?", ta.name as "to_account_name?",
+            ts_rank(t.search_vector, search.query) AS "rank!",
+            ts_headline('english', t.description, search.query, $3) AS "snippet!"
+        FROM transactions t
+        CROSS JOIN search
+        JOIN users u ON t.user_id = u.id
+        LEFT JOIN categories c ON t.category_id = c.id
+        LEFT JOIN category_hierarchy ch ON c.id = ch.category_id
+        LEFT JOIN transfers tr ON t.transfer_id = tr.id
+        LEFT JOIN accounts fa ON tr.from_account_id = fa.id
+        LEFT JOIN accounts ta ON tr.to_account_id = ta.id
+        WHERE t.user_id = $1
+        AND t.search_vector @@ search.query
+        ORDER BY ts_rank(t.search_vector, search.query) DESC, t.created_at DESC, t.id DESC
+        LIMIT $4
+        "#,
+        user.id,
+        terms,
+        headline_options,
+        limit
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|record| SearchResult {
+        transaction: Transaction {
+            id: record.transaction_id,
+            category: Category::from_columns(
+                record.category_id,
+                record.category_name,
+                record.parent_id,
+                record.category_created_at.map(convert_time_to_chrono)
+            ),
+            account_id: record.account_id,
+            transfer: TransferLink::from_columns(
+                record.transfer_id,
+                record.from_account_id,
+                record.from_account_name,
+                record.to_account_id,
+                record.to_account_name
+            ),
+            kind: TransactionKind::from_db(&record.kind),
+            description: record.description,
+            amount: record.amount.into(),
+            currency: record.currency.into(),
+            base_amount: record.base_amount.map(Into::into),
+            tags: record.tags,
+            splits: Vec::new(),
+            created_at: convert_time_to_chrono(record.transaction_created_at),
+        },
+        rank: record.rank,
+        snippet: highlight_snippet(&record.snippet),
+    })
+    .collect();
+
+    attach_splits(&pool, results.iter_mut().map(|result| &mut result.transaction)).await?;
+
+    Ok(Json(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wraps_matches_in_mark_tags() {
+        assert_eq!(highlight_snippet("Home \u{1}Hardware\u{2} store"), "Home <mark>Hardware</mark> store");
+    }
+
+    #[test]
+    fn test_escapes_description_html() {
+        assert_eq!(
+            highlight_snippet("<b>R&D</b> \u{1}tools\u{2}"),
+            "&lt;b&gt;R&amp;D&lt;/b&gt; <mark>tools</mark>"
+        );
+    }
+}
@@ -1,10 +1,9 @@
 use axum::{http::StatusCode, response::IntoResponse, routing::post, Extension, Json, Router};
-use axum_extra::extract::cookie::{Cookie, CookieJar};
+use axum_extra::extract::cookie::CookieJar;
 use sqlx::PgPool;
-use tower_cookies::cookie::SameSite;
 use uuid::Uuid;
 
-use crate::{models::user::NewUser, passwords::hash_password};
+use crate::{error::AppError, middleware::ClientInfo, models::user::NewUser, passwords::hash_password, sessions::{create_session, session_cookie}};
 
 pub fn routes() -> Router {
     Router::new().route("/signup", post(signup))
@@ -13,10 +12,12 @@ pub fn routes() -> Router {
 pub async fn signup(
     Extension(pool): Extension<PgPool>,
     jar: CookieJar,
+    client: ClientInfo,
     Json(payload): Json<NewUser>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, AppError> {
     if payload.password.len() < 8 {
-        return (StatusCode::BAD_REQUEST, "Password too short (minimum 8 characters)").into_response();
+        return Err(AppError::validation("Password too short (minimum 8 characters)")
+            .with_field("password", "Must be at least 8 characters"));
     }
 
     let existing_count = sqlx::query_scalar::<_, i64>(
@@ -24,37 +25,25 @@ pub async fn signup(
     )
     .bind(&payload.username)
     .fetch_one(&pool)
-    .await
-    .expect("Failed to check for existing username");
+    .await?;
 
     if existing_count > 0 {
-        return (StatusCode::CONFLICT, "Username already taken").into_response();
+        return Err(AppError::conflict("Username already taken").with_field("username", "Username already taken"));
     }
 
-    let password_hash = match hash_password(&payload.password) {
-        Ok(hash) => hash,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash password").into_response(),
-    };
+    let password_hash = hash_password(&payload.password).map_err(|_| AppError::internal())?;
 
     let user_id = Uuid::new_v4();
-    let result = sqlx::query(
+    sqlx::query(
         "INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3)"
     )
     .bind(user_id)
     .bind(&payload.username)
     .bind(&password_hash)
     .execute(&pool)
-    .await;
+    .await?;
 
-    if result.is_err() {
-        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create user").into_response();
-    }
-
-    let cookie = Cookie::build(("session", user_id.to_string()))
-        .path("/")
-        .http_only(true)
-        .secure(false)
-        .same_site(SameSite::Lax);
+    let token = create_session(&pool, user_id, &client).await?;
 
-    (jar.add(cookie), StatusCode::CREATED).into_response()
+    Ok((jar.add(session_cookie(token)), StatusCode::CREATED))
 }
@@ -0,0 +1,127 @@
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::{get, put}, Extension, Json, Router};
+use sqlx::{types::time::OffsetDateTime, PgPool};
+
+use crate::{
+    error::AppError,
+    alerts::evaluate_spending_limits,
+    middleware::AuthSession,
+    models::notification::{NewSpendingLimit, SpendingLimit},
+    time_conversion::convert_time_to_chrono
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/spending-limits", get(list_limits))
+        .route("/spending-limits/{category_id}", put(set_limit).delete(delete_limit))
+}
+
+const DEFAULT_THRESHOLDS: [i32; 2] = [80, 100];
+
+async fn list_limits(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let limits: Vec<SpendingLimit> = sqlx::query!(
+        r#"
+        SELECT l.category_id, l.amount, l.thresholds, l.created_at
+        FROM spending_limits l
+        JOIN categories c ON l.category_id = c.id
+        WHERE l.user_id = $1
+        ORDER BY c.name ASC
+        "#,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| SpendingLimit {
+        category_id: row.category_id,
+        amount: row.amount.into(),
+        thresholds: row.thresholds,
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(limits))
+}
+
+/// Creates or replaces the monthly spending limit of a category.
+async fn set_limit(
+    Path(category_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewSpendingLimit>,
+) -> Result<impl IntoResponse, AppError> {
+    if !payload.amount.is_positive() {
+        return Err(AppError::bad_request("Spending limit must be positive"));
+    }
+
+    let mut thresholds = payload.thresholds.unwrap_or_else(|| DEFAULT_THRESHOLDS.to_vec());
+    thresholds.sort_unstable();
+    thresholds.dedup();
+
+    if thresholds.is_empty() || thresholds.iter().any(|t| !(1..=1000).contains(t)) {
+        return Err(AppError::bad_request("Thresholds must be percentages between 1 and 1000"));
+    }
+
+    let category = sqlx::query!(
+        r#"SELECT id FROM categories WHERE id = $1 AND user_id = $2"#,
+        category_id,
+        user.id
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    if category.is_none() {
+        return Err(AppError::not_found("Category not found"));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO spending_limits (user_id, category_id, amount, thresholds)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (category_id) DO UPDATE
+        SET amount = EXCLUDED.amount, thresholds = EXCLUDED.thresholds
+        RETURNING category_id, amount, thresholds, created_at
+        "#,
+        user.id,
+        category_id,
+        payload.amount.as_decimal(),
+        &thresholds
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // The new limit may already be exceeded this month
+    evaluate_spending_limits(&mut *tx, user.id, &[OffsetDateTime::now_utc()]).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(SpendingLimit {
+        category_id: row.category_id,
+        amount: row.amount.into(),
+        thresholds: row.thresholds,
+        created_at: convert_time_to_chrono(row.created_at),
+    }))
+}
+
+async fn delete_limit(
+    Path(category_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let result = sqlx::query!(
+        r#"DELETE FROM spending_limits WHERE category_id = $1 AND user_id = $2"#,
+        category_id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Spending limit not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
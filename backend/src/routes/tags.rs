@@ -0,0 +1,269 @@
+use std::collections::BTreeSet;
+
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router};
+use sqlx::{PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::AuthSession,
+    models::{tag::{NewTag, Tag}, user::User},
+    time_conversion::convert_time_to_chrono
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/tags", get(list_tags).post(create_tag))
+        .route("/tags/{id}", get(get_tag).put(rename_tag).delete(delete_tag))
+}
+
+const MAX_TAG_LENGTH: usize = 50;
+
+/// Trims tag names and drops duplicates, returning them in alphabetical order.
+fn normalize_tags(names: &[String]) -> Result<Vec<String>, AppError> {
+    let mut normalized = BTreeSet::new();
+
+    for name in names {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("Tag names cannot be empty").with_field("tags", "Cannot be empty"));
+        }
+        if name.chars().count() > MAX_TAG_LENGTH {
+            return Err(AppError::validation(format!("Tag names can be at most {} characters", MAX_TAG_LENGTH))
+                .with_field("tags", "Too long"));
+        }
+        normalized.insert(name.to_string());
+    }
+
+    Ok(normalized.into_iter().collect())
+}
+
+/// Replaces a transaction's tags with `names`, creating any tags the user doesn't have yet.
+pub async fn set_transaction_tags(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    user_id: Uuid,
+    transaction_id: i32,
+    names: &[String],
+) -> Result<(), AppError> {
+    let names = normalize_tags(names)?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tags (user_id, name)
+        SELECT $1, name FROM UNNEST($2::text[]) AS name
+        ON CONFLICT (user_id, name) DO NOTHING
+        "#,
+        user_id,
+        &names
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM transaction_tags tt
+        USING tags tg
+        WHERE tg.id = tt.tag_id
+        AND tt.transaction_id = $1 AND tt.user_id = $2
+        AND NOT (tg.name = ANY($3))
+        "#,
+        transaction_id,
+        user_id,
+        &names
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO transaction_tags (transaction_id, tag_id, user_id)
+        SELECT $1, id, $2 FROM tags WHERE user_id = $2 AND name = ANY($3)
+        ON CONFLICT DO NOTHING
+        "#,
+        transaction_id,
+        user_id,
+        &names
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+async fn list_tags(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let rows: Vec<Tag> = sqlx::query!(
+        r#"
+        SELECT tg.id, tg.name, tg.created_at, COUNT(tt.transaction_id) AS "transaction_count!"
+        FROM tags tg
+        LEFT JOIN transaction_tags tt ON tt.tag_id = tg.id
+        WHERE tg.user_id = $1
+        GROUP BY tg.id
+        ORDER BY tg.name ASC
+        "#,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| Tag {
+        id: row.id,
+        name: row.name,
+        transaction_count: row.transaction_count,
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+async fn fetch_tag(pool: &PgPool, user: &User, id: i32) -> Result<Option<Tag>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT tg.id, tg.name, tg.created_at, COUNT(tt.transaction_id) AS "transaction_count!"
+        FROM tags tg
+        LEFT JOIN transaction_tags tt ON tt.tag_id = tg.id
+        WHERE tg.id = $1 AND tg.user_id = $2
+        GROUP BY tg.id
+        "#,
+        id,
+        user.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| Tag {
+        id: row.id,
+        name: row.name,
+        transaction_count: row.transaction_count,
+        created_at: convert_time_to_chrono(row.created_at),
+    }))
+}
+
+/// Validates a single tag name from a request body.
+fn tag_name(payload: &NewTag) -> Result<String, AppError> {
+    let mut names = normalize_tags(std::slice::from_ref(&payload.name))
+        .map_err(|_| AppError::validation("Invalid tag name").with_field("name", "Must be 1 to 50 characters"))?;
+
+    Ok(names.remove(0))
+}
+
+async fn create_tag(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewTag>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = tag_name(&payload)?;
+
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO tags (user_id, name)
+        VALUES ($1, $2)
+        RETURNING id, name, created_at
+        "#,
+        user.id,
+        name
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let tag = Tag {
+        id: record.id,
+        name: record.name,
+        transaction_count: 0,
+        created_at: convert_time_to_chrono(record.created_at),
+    };
+
+    Ok((StatusCode::CREATED, Json(tag)))
+}
+
+async fn get_tag(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    match fetch_tag(&pool, &user, id).await? {
+        Some(tag) => Ok(Json(tag)),
+        None => Err(AppError::not_found("Tag not found")),
+    }
+}
+
+/// Renames a tag everywhere it's used.
+async fn rename_tag(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewTag>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = tag_name(&payload)?;
+
+    let result = sqlx::query!(
+        r#"UPDATE tags SET name = $1 WHERE id = $2 AND user_id = $3"#,
+        name,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Tag not found"));
+    }
+
+    let tag = fetch_tag(&pool, &user, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Tag not found"))?;
+
+    Ok(Json(tag))
+}
+
+/// Deletes a tag and removes it from every transaction. The transactions themselves are kept.
+async fn delete_tag(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query!(
+        r#"DELETE FROM tags WHERE id = $1 AND user_id = $2"#,
+        id,
+        user.id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Tag not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_trims_dedupes_and_sorts() {
+        let normalized = normalize_tags(&names(&["  vacation-2026 ", "reimbursable", "vacation-2026"])).unwrap();
+
+        assert_eq!(normalized, names(&["reimbursable", "vacation-2026"]));
+    }
+
+    #[test]
+    fn test_rejects_blank_names() {
+        assert!(normalize_tags(&names(&["ok", "   "])).is_err());
+    }
+
+    #[test]
+    fn test_rejects_long_names() {
+        let long = "x".repeat(MAX_TAG_LENGTH + 1);
+
+        assert!(normalize_tags(&[long]).is_err());
+        assert!(normalize_tags(&["x".repeat(MAX_TAG_LENGTH)]).is_ok());
+    }
+}
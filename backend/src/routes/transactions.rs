@@ -1,153 +1,549 @@
-use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router};
-use sqlx::PgPool;
-use crate::{middleware::AuthSession, models::{category::Category, transaction::{NewTransaction, Transaction}, user::User}, time_conversion::{convert_chrono_to_time, convert_time_to_chrono}};
-use bigdecimal::{BigDecimal, ToPrimitive, FromPrimitive};
+use std::{collections::HashMap, str::FromStr};
+
+use axum::{extract::{Path, Query}, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router};
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::{types::BigDecimal, PgPool, Postgres};
+use uuid::Uuid;
+use crate::{alerts::evaluate_spending_limits, currency::Currency, error::AppError, middleware::AuthSession, models::{category::Category, transaction::{NewTransaction, SortDirection, Transaction, TransactionKind, TransactionPage, TransactionQuery, TransactionSort, TransactionSplit}, transfer::TransferLink, user::User}, money::Money, routes::{attachments::remove_stored_files, tags::set_transaction_tags, transfers::update_transfer_from_leg}, storage::Storage, time_conversion::{convert_chrono_to_date, convert_chrono_to_time, convert_time_to_chrono}, tokens::{from_hex, to_hex}};
 
 pub fn routes() -> Router {
     Router::new().route("/transactions", get(list_transactions).post(create_transaction))
         .route("/transactions/{id}", get(get_transaction).put(update_transaction).delete(delete_transaction))
 }
 
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// Where the previous page stopped: the sort it was fetched with, and the sort value and id of its last row.
+struct Cursor {
+    sort: TransactionSort,
+    id: i32,
+    value: String,
+}
+
+fn encode_cursor(sort: TransactionSort, id: i32, value: &str) -> String {
+    to_hex(format!("{}:{}:{}", sort.as_str(), id, value).as_bytes())
+}
+
+fn decode_cursor(cursor: &str) -> Option<Cursor> {
+    let decoded = String::from_utf8(from_hex(cursor)?).ok()?;
+    let (sort, rest) = decoded.split_once(':')?;
+    let (id, value) = rest.split_once(':')?;
+
+    let sort = match sort {
+        "created_at" => TransactionSort::CreatedAt,
+        "amount" => TransactionSort::Amount,
+        "description" => TransactionSort::Description,
+        _ => return None,
+    };
+
+    Some(Cursor { sort, id: id.parse().ok()?, value: value.to_string() })
+}
+
+/// Turns a user-supplied substring into an `ILIKE` pattern that matches it literally.
+fn contains_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
 async fn list_transactions(
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession,
-) -> impl IntoResponse {
-    let rows: Vec<Transaction> = sqlx::query!(
+    Query(query): Query<TransactionQuery>,
+) -> Result<Json<TransactionPage>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let ascending = query.direction == SortDirection::Asc;
+    let description = query.description.as_deref().map(contains_pattern);
+    let tag = query.tag.as_deref().map(str::trim);
+
+    // The cursor's sort value is bound to whichever parameter matches the sort field
+    let mut cursor_id = None;
+    let mut cursor_time = None;
+    let mut cursor_amount = None;
+    let mut cursor_text = None;
+
+    if let Some(cursor) = &query.cursor {
+        let cursor = decode_cursor(cursor)
+            .filter(|cursor| cursor.sort == query.sort)
+            .ok_or(AppError::bad_request("Invalid cursor"))?;
+
+        match cursor.sort {
+            TransactionSort::CreatedAt => {
+                let time = DateTime::parse_from_rfc3339(&cursor.value)
+                    .map_err(|_| AppError::bad_request("Invalid cursor"))?;
+                cursor_time = Some(convert_chrono_to_time(time.with_timezone(&Utc)));
+            }
+            TransactionSort::Amount => {
+                let amount = BigDecimal::from_str(&cursor.value)
+                    .map_err(|_| AppError::bad_request("Invalid cursor"))?;
+                cursor_amount = Some(amount);
+            }
+            TransactionSort::Description => cursor_text = Some(cursor.value),
+        }
+
+        cursor_id = Some(cursor.id);
+    }
+
+    let total = sqlx::query!(
         r#"
+        WITH RECURSIVE category_tree AS (
+            SELECT id FROM categories WHERE id = $2 AND user_id = $1
+            UNION ALL
+            SELECT ch.category_id
+            FROM category_hierarchy ch
+            INNER JOIN category_tree ct ON ch.parent_id = ct.id
+            WHERE $3
+        )
+        SELECT COUNT(*) AS "total!"
+        FROM transactions t
+        WHERE t.user_id = $1
+        AND ($2::int IS NULL OR t.id IN (
+            SELECT transaction_id FROM categorized_transactions WHERE category_id IN (SELECT id FROM category_tree)
+        ))
+        AND ($4::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date >= $4)
+        AND ($5::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date <= $5)
+        AND ($6::numeric IS NULL OR t.amount >= $6)
+        AND ($7::numeric IS NULL OR t.amount <= $7)
+        AND ($8::text IS NULL OR t.description ILIKE $8)
+        AND ($9::text IS NULL OR EXISTS (
+            SELECT 1 FROM transaction_tags tt JOIN tags tg ON tg.id = tt.tag_id
+            WHERE tt.transaction_id = t.id AND tg.name = $9
+        ))
+        "#,
+        user.id,
+        query.category_id,
+        query.include_descendants,
+        query.from.map(convert_chrono_to_date),
+        query.to.map(convert_chrono_to_date),
+        query.min_amount.as_ref().map(Money::as_decimal),
+        query.max_amount.as_ref().map(Money::as_decimal),
+        description,
+        tag
+    )
+    .fetch_one(&pool)
+    .await?
+    .total;
+
+    // One extra row tells us whether there is another page
+    let mut rows = sqlx::query!(
+        r#"
+        WITH RECURSIVE category_tree AS (
+            -- Base case: the requested category
+            SELECT id FROM categories WHERE id = $2 AND user_id = $1
+            UNION ALL
+            -- Recursive step: its subcategories, when asked for
+            SELECT ch.category_id
+            FROM category_hierarchy ch
+            INNER JOIN category_tree ct ON ch.parent_id = ct.id
+            WHERE $3
+        )
         SELECT
-            transactions.id as transaction_id,
-            categories.id as category_id,
-            transactions.description as transaction_description,
-            categories.name as category_name,
-            amount,
-            transactions.created_at as transaction_created_at,
-            categories.created_at as category_created_at,
-            ch.parent_id as "parent_id?"
-        FROM transactions
-        JOIN categories ON transactions.category_id = categories.id
+            t.id as transaction_id,
+            categories.id as "category_id?",
+            t.account_id,
+            t.kind,
+            t.description as transaction_description,
+            categories.name as "category_name?",
+            t.amount,
+            t.currency,
+            ROUND(t.amount * exchange_rate_on(t.user_id, t.currency, users.base_currency, t.created_at), 4) AS base_amount,
+            t.created_at as transaction_created_at,
+            transaction_tag_names(t.id) AS "tags!",
+            categories.created_at as "category_created_at?",
+            ch.parent_id as "parent_id?",
+            tr.id as "transfer_id?",
+            fa.id as "from_account_id?", fa.name as "from_account_name?",
+            ta.id as "to_account_id?", ta.name as "to_account_name?"
+        FROM transactions t
+        JOIN users ON t.user_id = users.id
+        LEFT JOIN categories ON t.category_id = categories.id
         LEFT JOIN category_hierarchy ch ON categories.id = ch.category_id
-        WHERE transactions.user_id = $1
-        ORDER BY transactions.created_at DESC
+        LEFT JOIN transfers tr ON t.transfer_id = tr.id
+        LEFT JOIN accounts fa ON tr.from_account_id = fa.id
+        LEFT JOIN accounts ta ON tr.to_account_id = ta.id
+        WHERE t.user_id = $1
+        AND ($2::int IS NULL OR t.id IN (
+            SELECT transaction_id FROM categorized_transactions WHERE category_id IN (SELECT id FROM category_tree)
+        ))
+        AND ($4::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date >= $4)
+        AND ($5::date IS NULL OR (t.created_at AT TIME ZONE 'UTC')::date <= $5)
+        AND ($6::numeric IS NULL OR t.amount >= $6)
+        AND ($7::numeric IS NULL OR t.amount <= $7)
+        AND ($8::text IS NULL OR t.description ILIKE $8)
+        AND ($16::text IS NULL OR EXISTS (
+            SELECT 1 FROM transaction_tags tt JOIN tags tg ON tg.id = tt.tag_id
+            WHERE tt.transaction_id = t.id AND tg.name = $16
+        ))
+        -- Keyset pagination: continue strictly after the cursor's row in the requested order
+        AND ($11::int IS NULL OR CASE $9::text
+            WHEN 'amount' THEN CASE WHEN $10::bool
+                THEN (t.amount, t.id) > ($13::numeric, $11)
+                ELSE (t.amount, t.id) < ($13::numeric, $11) END
+            WHEN 'description' THEN CASE WHEN $10::bool
+                THEN (t.description, t.id) > ($14::text, $11)
+                ELSE (t.description, t.id) < ($14::text, $11) END
+            ELSE CASE WHEN $10::bool
+                THEN (t.created_at, t.id) > ($12::timestamptz, $11)
+                ELSE (t.created_at, t.id) < ($12::timestamptz, $11) END
+        END)
+        ORDER BY
+            CASE WHEN $9 = 'amount' AND $10 THEN t.amount END ASC,
+            CASE WHEN $9 = 'amount' AND NOT $10 THEN t.amount END DESC,
+            CASE WHEN $9 = 'description' AND $10 THEN t.description END ASC,
+            CASE WHEN $9 = 'description' AND NOT $10 THEN t.description END DESC,
+            CASE WHEN $9 = 'created_at' AND $10 THEN t.created_at END ASC,
+            CASE WHEN $9 = 'created_at' AND NOT $10 THEN t.created_at END DESC,
+            CASE WHEN $10 THEN t.id END ASC,
+            CASE WHEN NOT $10 THEN t.id END DESC
+        LIMIT $15::bigint + 1
         "#,
-        user.id
+        user.id,
+        query.category_id,
+        query.include_descendants,
+        query.from.map(convert_chrono_to_date),
+        query.to.map(convert_chrono_to_date),
+        query.min_amount.as_ref().map(Money::as_decimal),
+        query.max_amount.as_ref().map(Money::as_decimal),
+        description,
+        query.sort.as_str(),
+        ascending,
+        cursor_id,
+        cursor_time,
+        cursor_amount,
+        cursor_text,
+        limit,
+        tag
     )
     .fetch_all(&pool)
-    .await
-    .expect("Failed to fetch transactions")
-    .into_iter()
-    .map(|row| Transaction {
-        id: row.transaction_id,
-        category: Category {
-            id: row.category_id,
-            name: row.category_name,
-            parent_id: row.parent_id, 
-            created_at: convert_time_to_chrono(row.category_created_at)
-        },
-        description: row.transaction_description,
-        amount: row.amount.to_f64().unwrap_or(0.0),
-        created_at: convert_time_to_chrono(row.transaction_created_at)
-    })
-    .collect(); 
-
-    Json(rows)
+    .await?;
+
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|row| {
+            let value = match query.sort {
+                TransactionSort::CreatedAt => convert_time_to_chrono(row.transaction_created_at)
+                    .to_rfc3339_opts(SecondsFormat::Micros, true),
+                TransactionSort::Amount => row.amount.to_string(),
+                TransactionSort::Description => row.transaction_description.clone(),
+            };
+            encode_cursor(query.sort, row.transaction_id, &value)
+        })
+    } else {
+        None
+    };
+
+    let mut transactions: Vec<Transaction> = rows
+        .into_iter()
+        .map(|row| Transaction {
+            id: row.transaction_id,
+            category: Category::from_columns(
+                row.category_id,
+                row.category_name,
+                row.parent_id,
+                row.category_created_at.map(convert_time_to_chrono)
+            ),
+            account_id: row.account_id,
+            transfer: TransferLink::from_columns(
+                row.transfer_id,
+                row.from_account_id,
+                row.from_account_name,
+                row.to_account_id,
+                row.to_account_name
+            ),
+            kind: TransactionKind::from_db(&row.kind),
+            description: row.transaction_description,
+            amount: row.amount.into(),
+            currency: row.currency.into(),
+            base_amount: row.base_amount.map(Into::into),
+            tags: row.tags,
+            splits: Vec::new(),
+            created_at: convert_time_to_chrono(row.transaction_created_at)
+        })
+        .collect();
+
+    attach_splits(&pool, &mut transactions).await?;
+
+    Ok(Json(TransactionPage {
+        transactions,
+        total,
+        next_cursor,
+    }))
+}
+
+/// Works out which currency a transaction is recorded in. Transactions in an account
+/// must use the account's currency so its balance stays a plain sum.
+pub async fn resolve_currency(
+    pool: &PgPool,
+    user: &User,
+    payload: &NewTransaction,
+) -> Result<Currency, AppError> {
+    let Some(account_id) = payload.account_id.flatten() else {
+        return Ok(payload.currency.clone().unwrap_or_else(|| user.base_currency.clone()));
+    };
+
+    let account = sqlx::query!(
+        r#"SELECT currency FROM accounts WHERE id = $1 AND user_id = $2"#,
+        account_id,
+        user.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(account) = account else {
+        return Err(AppError::validation("Unknown account").with_field("account_id", "Account not found"));
+    };
+
+    let account_currency = Currency::from(account.currency);
+    match &payload.currency {
+        Some(currency) if *currency != account_currency => {
+            Err(AppError::validation("Transaction currency must match its account")
+                .with_field("currency", "Must match the account's currency"))
+        }
+        _ => Ok(account_currency),
+    }
+}
+
+/// Works out the transaction's kind and checks the amount's sign agrees with it.
+/// Transfers have their own endpoint so both legs stay in sync.
+pub fn resolve_kind(payload: &NewTransaction) -> Result<TransactionKind, AppError> {
+    let kind = payload.kind.unwrap_or_else(|| TransactionKind::from_amount(&payload.amount));
+
+    match kind {
+        TransactionKind::Transfer => {
+            Err(AppError::validation("Use /transfers to record transfers").with_field("kind", "Cannot be transfer"))
+        }
+        kind if !kind.accepts(&payload.amount) => {
+            Err(AppError::validation("Expenses must be negative and income positive")
+                .with_field("amount", "Sign doesn't match the transaction kind"))
+        }
+        kind => Ok(kind),
+    }
+}
+
+/// Checks that a transaction's splits add up to its amount, and that each one has the sign of its
+/// kind, so an expense can't hide income in one of its parts.
+fn validate_splits(kind: TransactionKind, amount: &Money, splits: &[TransactionSplit]) -> Result<(), AppError> {
+    if let Some(split) = splits.iter().find(|split| !kind.accepts(&split.amount)) {
+        return Err(AppError::validation(format!("Split of {} doesn't match the transaction's kind", split.amount))
+            .with_field("splits", "Expense splits must be negative and income splits positive"));
+    }
+
+    let total: BigDecimal = splits.iter().map(|split| split.amount.as_decimal()).sum();
+
+    if !splits.is_empty() && total != *amount.as_decimal() {
+        return Err(AppError::validation(format!("Splits add up to {} but the transaction amount is {}", Money::from(total), amount))
+            .with_field("splits", "Must add up to the transaction amount"));
+    }
+
+    Ok(())
+}
+
+/// Replaces a transaction's splits. The database checks again that they add up once `tx` commits.
+pub async fn set_transaction_splits(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    user_id: Uuid,
+    transaction_id: i32,
+    splits: &[TransactionSplit],
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"DELETE FROM transaction_splits WHERE transaction_id = $1 AND user_id = $2"#,
+        transaction_id,
+        user_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let category_ids: Vec<i32> = splits.iter().map(|split| split.category_id).collect();
+    let amounts: Vec<BigDecimal> = splits.iter().map(|split| split.amount.as_decimal().clone()).collect();
+    let memos: Vec<Option<String>> = splits.iter().map(|split| split.memo.clone()).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO transaction_splits (transaction_id, user_id, category_id, amount, memo)
+        SELECT $1, $2, split.category_id, split.amount, split.memo
+        FROM UNNEST($3::int[], $4::numeric[], $5::text[]) WITH ORDINALITY AS split(category_id, amount, memo, position)
+        ORDER BY split.position
+        "#,
+        transaction_id,
+        user_id,
+        &category_ids,
+        &amounts,
+        &memos as &[Option<String>]
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Loads the splits of every transaction in `transactions` with a single query.
+pub async fn attach_splits<'a>(
+    pool: &PgPool,
+    transactions: impl IntoIterator<Item = &'a mut Transaction>,
+) -> Result<(), AppError> {
+    let transactions: Vec<&mut Transaction> = transactions.into_iter().collect();
+    let ids: Vec<i32> = transactions.iter().map(|transaction| transaction.id).collect();
+
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT transaction_id, category_id, amount, memo
+        FROM transaction_splits
+        WHERE transaction_id = ANY($1)
+        ORDER BY id
+        "#,
+        &ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut splits_of: HashMap<i32, Vec<TransactionSplit>> = HashMap::new();
+    for row in rows {
+        splits_of.entry(row.transaction_id).or_default().push(TransactionSplit {
+            category_id: row.category_id,
+            amount: row.amount.into(),
+            memo: row.memo,
+        });
+    }
+
+    for transaction in transactions {
+        transaction.splits = splits_of.remove(&transaction.id).unwrap_or_default();
+    }
+
+    Ok(())
 }
 
 pub async fn create_transaction(
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession,
     Json(payload): Json<NewTransaction>,
-) -> Json<Transaction> {
+) -> Result<Json<Transaction>, AppError> {
+    let kind = resolve_kind(&payload)?;
+    let currency = resolve_currency(&pool, &user, &payload).await?;
+
+    if let Some(splits) = &payload.splits {
+        validate_splits(kind, &payload.amount, splits)?;
+    }
+
+    let mut tx = pool.begin().await?;
+
     let record = sqlx::query!(
         r#"
-        INSERT INTO transactions (user_id, category_id, description, amount, created_at)
-        VALUES ($1, $2, $3, $4, COALESCE($5, now()))
-        RETURNING id, category_id, description, amount, created_at
+        INSERT INTO transactions (user_id, category_id, account_id, kind, description, amount, currency, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, now()))
+        RETURNING id, created_at
         "#,
         user.id,
         payload.category_id,
+        payload.account_id.flatten(),
+        kind.as_str(),
         payload.description,
-        BigDecimal::from_f64(payload.amount),
+        payload.amount.as_decimal(),
+        currency.as_str(),
         payload.created_at.map(convert_chrono_to_time)
     )
-    .fetch_one(&pool)
-    .await
-    .expect("Failed to insert transaction");
+    .fetch_one(&mut *tx)
+    .await?;
 
-    let result = Transaction {
-        id: record.id,
-        category: fetch_category(axum::Extension(pool), &user, record.category_id).await,
-        description: record.description,
-        amount: record.amount.to_f64().unwrap_or(0.0),
-        created_at: convert_time_to_chrono(record.created_at)
-    };
+    if let Some(tags) = &payload.tags {
+        set_transaction_tags(&mut tx, user.id, record.id, tags).await?;
+    }
+
+    if let Some(splits) = &payload.splits {
+        set_transaction_splits(&mut tx, user.id, record.id, splits).await?;
+    }
 
-    Json(result)
+    evaluate_spending_limits(&mut *tx, user.id, &[record.created_at]).await?;
+
+    tx.commit().await?;
+
+    let result = fetch_transaction(&pool, &user, record.id)
+        .await?
+        .ok_or_else(AppError::internal)?;
+
+    Ok(Json(result))
 }
 
-async fn fetch_category(
-    Extension(pool): Extension<PgPool>,
+/// Loads a single transaction with its category and base-currency amount.
+async fn fetch_transaction(
+    pool: &PgPool,
     user: &User,
-    category_id: i32,
-) -> Category {
+    id: i32,
+) -> Result<Option<Transaction>, AppError> {
     let record = sqlx::query!(
         r#"
-        SELECT c.id, c.name, c.created_at, ch.parent_id as "parent_id?"
-        FROM categories c
+        SELECT
+            t.id AS transaction_id, t.description, t.amount, t.currency, t.account_id, t.kind,
+            ROUND(t.amount * exchange_rate_on(t.user_id, t.currency, u.base_currency, t.created_at), 4) AS base_amount,
+            t.created_at AS transaction_created_at,
+            transaction_tag_names(t.id) AS "tags!",
+            c.id AS "category_id?", c.name AS "category_name?", c.created_at AS "category_created_at?",
+            ch.parent_id as "parent_id?",
+            tr.id as "transfer_id?",
+            fa.id as "from_account_id?", fa.name as "from_account_name?",
+            ta.id as "to_account_id?", ta.name as "to_account_name?"
+        FROM transactions t
+        JOIN users u ON t.user_id = u.id
+        LEFT JOIN categories c ON t.category_id = c.id
         LEFT JOIN category_hierarchy ch ON c.id = ch.category_id
-        WHERE c.user_id = $1
-        AND c.id = $2
-        ORDER BY c.id DESC
+        LEFT JOIN transfers tr ON t.transfer_id = tr.id
+        LEFT JOIN accounts fa ON tr.from_account_id = fa.id
+        LEFT JOIN accounts ta ON tr.to_account_id = ta.id
+        WHERE t.id = $1 AND t.user_id = $2
         "#,
-        user.id,
-        category_id
+        id,
+        user.id
     )
-    .fetch_one(&pool)
-    .await
-    .expect("Failed to fetch category");
+    .fetch_optional(pool)
+    .await?;
 
-    Category {
-        id: record.id,
-        name: record.name,
-        parent_id: record.parent_id, 
-        created_at: convert_time_to_chrono(record.created_at),
-    }
+    let Some(record) = record else {
+        return Ok(None);
+    };
+
+    let mut transaction = Transaction {
+        id: record.transaction_id,
+        category: Category::from_columns(
+            record.category_id,
+            record.category_name,
+            record.parent_id,
+            record.category_created_at.map(convert_time_to_chrono)
+        ),
+        account_id: record.account_id,
+        transfer: TransferLink::from_columns(
+            record.transfer_id,
+            record.from_account_id,
+            record.from_account_name,
+            record.to_account_id,
+            record.to_account_name
+        ),
+        kind: TransactionKind::from_db(&record.kind),
+        description: record.description,
+        amount: record.amount.into(),
+        currency: record.currency.into(),
+        base_amount: record.base_amount.map(Into::into),
+        tags: record.tags,
+        splits: Vec::new(),
+        created_at: convert_time_to_chrono(record.transaction_created_at),
+    };
+
+    attach_splits(pool, [&mut transaction]).await?;
+
+    Ok(Some(transaction))
 }
 
 async fn get_transaction(
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession
-) -> impl IntoResponse {
-    let existing = sqlx::query!(
-        r#"
-        SELECT id, description, amount, created_at, category_id
-        FROM transactions
-        WHERE id = $1 AND user_id = $2
-        "#,
-        id,
-        user.id
-    )
-    .fetch_optional(&pool)
-    .await
-    .expect("Failed to fetch transaction");
-
-    if existing.is_none() {
-        return Err(StatusCode::NOT_FOUND);
+) -> Result<impl IntoResponse, AppError> {
+    match fetch_transaction(&pool, &user, id).await? {
+        Some(transaction) => Ok(Json(transaction)),
+        None => Err(AppError::not_found("Transaction not found")),
     }
-
-    let row = existing.unwrap();
-
-    let category = fetch_category(Extension(pool), &user, row.category_id).await;
-
-    let transaction = Transaction {
-        id: row.id,
-        description: row.description,
-        amount: row.amount.to_f64().unwrap_or(0.0),
-        created_at: convert_time_to_chrono(row.created_at),
-        category,
-    };
-
-    Ok(Json(transaction))
 }
 
 async fn update_transaction(
@@ -155,76 +551,237 @@ async fn update_transaction(
     Extension(pool): Extension<PgPool>,
     AuthSession(user): AuthSession,
     Json(payload): Json<NewTransaction>,
-) -> impl IntoResponse {
-    let existing = sqlx::query!(
-        r#"
-        SELECT id FROM transactions
-        WHERE id = $1 AND user_id = $2
-        "#,
+) -> Result<impl IntoResponse, AppError> {
+    // Editing either leg of a transfer edits the transfer as a whole
+    let transfer_id = sqlx::query!(
+        r#"SELECT transfer_id FROM transactions WHERE id = $1 AND user_id = $2"#,
         id,
         user.id
     )
     .fetch_optional(&pool)
-    .await
-    .expect("Failed to fetch transaction");
+    .await?
+    .and_then(|row| row.transfer_id);
+
+    if let Some(transfer_id) = transfer_id {
+        if payload.splits.as_ref().is_some_and(|splits| !splits.is_empty()) {
+            return Err(AppError::validation("Transfers can't be split across categories")
+                .with_field("splits", "Not allowed on transfers"));
+        }
+
+        let mut tx = pool.begin().await?;
+
+        update_transfer_from_leg(&mut tx, &user, transfer_id, id, &payload).await?;
 
-    if existing.is_none() {
-        return Err(StatusCode::NOT_FOUND);
+        if let Some(tags) = &payload.tags {
+            set_transaction_tags(&mut tx, user.id, id, tags).await?;
+        }
+
+        tx.commit().await?;
+
+        let updated = fetch_transaction(&pool, &user, id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Transaction not found"))?;
+        return Ok(Json(updated));
+    }
+
+    let kind = resolve_kind(&payload)?;
+
+    if let Some(splits) = &payload.splits {
+        validate_splits(kind, &payload.amount, splits)?;
     }
 
+    // Keep the stored currency when neither an account nor a currency is given
+    let currency = match (payload.account_id.flatten(), &payload.currency) {
+        (None, None) => None,
+        (Some(_), None) => {
+            let account_currency = resolve_currency(&pool, &user, &payload).await?;
+
+            // The amount was entered in the stored currency; don't silently relabel it
+            let stored = sqlx::query_scalar!(
+                r#"SELECT currency FROM transactions WHERE id = $1 AND user_id = $2"#,
+                id,
+                user.id
+            )
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Transaction not found"))?;
+
+            if stored != account_currency.as_str() {
+                return Err(AppError::validation(format!("The transaction is in {} but the account is in {}", stored, account_currency))
+                    .with_field("currency", "Send the currency explicitly when moving to an account in another currency"));
+            }
+
+            Some(account_currency)
+        }
+        _ => Some(resolve_currency(&pool, &user, &payload).await?),
+    };
+
+    let mut tx = pool.begin().await?;
+
     let row = sqlx::query!(
         r#"
         UPDATE transactions
         SET
             description = $1,
             amount = $2,
-            created_at = COALESCE($3, created_at),
-            category_id = $4
-        WHERE id = $5
-        RETURNING id, description, amount, created_at, category_id
+            currency = COALESCE($3, currency),
+            created_at = COALESCE($4, created_at),
+            category_id = $5,
+            account_id = CASE WHEN $10 THEN $6 ELSE account_id END,
+            kind = $7
+        WHERE id = $8 AND user_id = $9
+        RETURNING id, created_at
         "#,
         payload.description,
-        BigDecimal::from_f64(payload.amount),
+        payload.amount.as_decimal(),
+        currency.as_ref().map(|currency| currency.as_str()),
         payload.created_at.map(convert_chrono_to_time),
         payload.category_id,
-        id
+        payload.account_id.flatten(),
+        kind.as_str(),
+        id,
+        user.id,
+        payload.account_id.is_some()
     )
-    .fetch_one(&pool)
-    .await
-    .expect("Failed to update transaction");
-
-    let category = fetch_category(Extension(pool), &user, row.category_id).await;
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    let updated = Transaction {
-        id: row.id,
-        description: row.description,
-        amount: row.amount.to_f64().unwrap_or(0.0),
-        created_at: convert_time_to_chrono(row.created_at),
-        category,
+    let Some(row) = row else {
+        return Err(AppError::not_found("Transaction not found"));
     };
 
+    if let Some(tags) = &payload.tags {
+        set_transaction_tags(&mut tx, user.id, id, tags).await?;
+    }
+
+    if let Some(splits) = &payload.splits {
+        set_transaction_splits(&mut tx, user.id, id, splits).await?;
+    }
+
+    evaluate_spending_limits(&mut *tx, user.id, &[row.created_at]).await?;
+
+    tx.commit().await?;
+
+    let updated = fetch_transaction(&pool, &user, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Transaction not found"))?;
+
     Ok(Json(updated))
 }
 
 async fn delete_transaction(
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
+    Extension(storage): Extension<Storage>,
     AuthSession(user): AuthSession
-) -> impl IntoResponse {
-    let result = sqlx::query!(
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // The attachment rows would go with the cascade, but their files have to be removed by hand
+    let storage_keys = sqlx::query_scalar!(
         r#"
-        DELETE FROM transactions
-        WHERE id = $1 AND user_id = $2
+        DELETE FROM attachments
+        WHERE user_id = $2
+        AND transaction_id IN (
+            SELECT id FROM transactions
+            WHERE user_id = $2
+            AND (id = $1 OR transfer_id = (SELECT transfer_id FROM transactions WHERE id = $1 AND user_id = $2))
+        )
+        RETURNING storage_key
         "#,
         id,
         user.id
     )
-    .execute(&pool)
-    .await;
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // Deleting either leg of a transfer removes the transfer and, through the cascade, both legs
+    let deleted_transfer = sqlx::query!(
+        r#"
+        DELETE FROM transfers
+        WHERE id = (SELECT transfer_id FROM transactions WHERE id = $1 AND user_id = $2)
+        AND user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if deleted_transfer.rows_affected() == 0 {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM transactions
+            WHERE id = $1 AND user_id = $2
+            "#,
+            id,
+            user.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Transaction not found"));
+        }
+    }
+
+    tx.commit().await?;
+
+    remove_stored_files(&storage, &storage_keys).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = encode_cursor(TransactionSort::Description, 42, "Coffee: large");
+        let decoded = decode_cursor(&cursor).unwrap();
+
+        assert!(decoded.sort == TransactionSort::Description);
+        assert_eq!(decoded.id, 42);
+        assert_eq!(decoded.value, "Coffee: large");
+    }
+
+    #[test]
+    fn test_rejects_malformed_cursors() {
+        assert!(decode_cursor("not hex").is_none());
+        assert!(decode_cursor(&to_hex(b"rating:1:5")).is_none());
+        assert!(decode_cursor(&to_hex(b"amount:x:5")).is_none());
+    }
+
+    #[test]
+    fn test_escapes_like_wildcards() {
+        assert_eq!(contains_pattern("coffee"), "%coffee%");
+        assert_eq!(contains_pattern("50%_off\\"), "%50\\%\\_off\\\\%");
+    }
+
+    fn split(category_id: i32, amount: &str) -> TransactionSplit {
+        TransactionSplit { category_id, amount: amount.parse().unwrap(), memo: None }
+    }
+
+    #[test]
+    fn test_splits_must_add_up() {
+        let amount: Money = "-120.50".parse().unwrap();
+
+        assert!(validate_splits(TransactionKind::Expense, &amount, &[split(1, "-80"), split(2, "-40.5")]).is_ok());
+        assert!(validate_splits(TransactionKind::Expense, &amount, &[split(1, "-80"), split(2, "-40")]).is_err());
+    }
+
+    #[test]
+    fn test_splits_must_match_the_kind() {
+        let amount: Money = "-10".parse().unwrap();
+
+        assert!(validate_splits(TransactionKind::Expense, &amount, &[split(1, "40"), split(2, "-50")]).is_err());
+        assert!(validate_splits(TransactionKind::Income, &"10".parse().unwrap(), &[split(1, "-5"), split(2, "15")]).is_err());
+    }
 
-    match result {
-        Ok(res) if res.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
-        Ok(_) => Ok(StatusCode::NO_CONTENT),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    #[test]
+    fn test_no_splits_is_always_valid() {
+        assert!(validate_splits(TransactionKind::Expense, &"-5".parse().unwrap(), &[]).is_ok());
     }
 }
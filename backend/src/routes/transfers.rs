@@ -0,0 +1,400 @@
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router};
+use sqlx::{PgExecutor, PgPool, Postgres};
+
+use crate::{
+    currency::Currency,
+    error::AppError,
+    middleware::AuthSession,
+    models::{transaction::{NewTransaction, TransactionKind}, transfer::{NewTransfer, Transfer}, user::User},
+    money::Money,
+    routes::attachments::remove_stored_files,
+    storage::Storage,
+    time_conversion::{convert_chrono_to_time, convert_time_to_chrono}
+};
+
+pub fn routes() -> Router {
+    Router::new().route("/transfers", get(list_transfers).post(create_transfer))
+        .route("/transfers/{id}", get(get_transfer).put(update_transfer).delete(delete_transfer))
+}
+
+async fn list_transfers(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+) -> Result<impl IntoResponse, AppError> {
+    let rows: Vec<Transfer> = sqlx::query!(
+        r#"
+        SELECT
+            tr.id, fl.description, fl.created_at,
+            fa.id AS from_account_id, fa.name AS from_account_name, fa.currency AS from_currency,
+            fl.id AS from_transaction_id, -fl.amount AS "amount!",
+            ta.id AS to_account_id, ta.name AS to_account_name, ta.currency AS to_currency,
+            tl.id AS to_transaction_id, tl.amount AS to_amount
+        FROM transfers tr
+        JOIN accounts fa ON fa.id = tr.from_account_id
+        JOIN accounts ta ON ta.id = tr.to_account_id
+        JOIN transactions fl ON fl.transfer_id = tr.id AND fl.account_id = tr.from_account_id
+        JOIN transactions tl ON tl.transfer_id = tr.id AND tl.account_id = tr.to_account_id
+        WHERE tr.user_id = $1
+        ORDER BY fl.created_at DESC, tr.id DESC
+        "#,
+        user.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| Transfer {
+        id: row.id,
+        description: row.description,
+        from_account_id: row.from_account_id,
+        from_account_name: row.from_account_name,
+        amount: row.amount.into(),
+        from_currency: row.from_currency.into(),
+        from_transaction_id: row.from_transaction_id,
+        to_account_id: row.to_account_id,
+        to_account_name: row.to_account_name,
+        to_amount: row.to_amount.into(),
+        to_currency: row.to_currency.into(),
+        to_transaction_id: row.to_transaction_id,
+        created_at: convert_time_to_chrono(row.created_at),
+    })
+    .collect();
+
+    Ok(Json(rows))
+}
+
+/// Loads a transfer together with both of its legs.
+async fn fetch_transfer<'e>(executor: impl PgExecutor<'e>, user: &User, id: i32) -> Result<Option<Transfer>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            tr.id, fl.description, fl.created_at,
+            fa.id AS from_account_id, fa.name AS from_account_name, fa.currency AS from_currency,
+            fl.id AS from_transaction_id, -fl.amount AS "amount!",
+            ta.id AS to_account_id, ta.name AS to_account_name, ta.currency AS to_currency,
+            tl.id AS to_transaction_id, tl.amount AS to_amount
+        FROM transfers tr
+        JOIN accounts fa ON fa.id = tr.from_account_id
+        JOIN accounts ta ON ta.id = tr.to_account_id
+        JOIN transactions fl ON fl.transfer_id = tr.id AND fl.account_id = tr.from_account_id
+        JOIN transactions tl ON tl.transfer_id = tr.id AND tl.account_id = tr.to_account_id
+        WHERE tr.id = $1 AND tr.user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some(Transfer {
+        id: row.id,
+        description: row.description,
+        from_account_id: row.from_account_id,
+        from_account_name: row.from_account_name,
+        amount: row.amount.into(),
+        from_currency: row.from_currency.into(),
+        from_transaction_id: row.from_transaction_id,
+        to_account_id: row.to_account_id,
+        to_account_name: row.to_account_name,
+        to_amount: row.to_amount.into(),
+        to_currency: row.to_currency.into(),
+        to_transaction_id: row.to_transaction_id,
+        created_at: convert_time_to_chrono(row.created_at),
+    }))
+}
+
+/// Checks both accounts and works out how much arrives in the destination account.
+async fn validate_transfer<'e>(executor: impl PgExecutor<'e>, user: &User, payload: &NewTransfer) -> Result<(Currency, Currency, Money), AppError> {
+    if payload.from_account_id == payload.to_account_id {
+        return Err(AppError::validation("Cannot transfer to the same account")
+            .with_field("to_account_id", "Must differ from the source account"));
+    }
+
+    if !payload.amount.is_positive() || payload.to_amount.as_ref().is_some_and(|amount| !amount.is_positive()) {
+        return Err(AppError::validation("Transfer amounts must be positive")
+            .with_field("amount", "Must be positive"));
+    }
+
+    let accounts = sqlx::query!(
+        r#"
+        SELECT id, currency FROM accounts
+        WHERE id = ANY($1) AND user_id = $2
+        "#,
+        &[payload.from_account_id, payload.to_account_id],
+        user.id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    let currency_of = |account_id: i32| {
+        accounts.iter()
+            .find(|account| account.id == account_id)
+            .map(|account| Currency::from(account.currency.clone()))
+    };
+
+    let (Some(from_currency), Some(to_currency)) = (currency_of(payload.from_account_id), currency_of(payload.to_account_id)) else {
+        return Err(AppError::validation("Unknown account"));
+    };
+
+    let to_amount = match &payload.to_amount {
+        Some(to_amount) if from_currency == to_currency && *to_amount != payload.amount => {
+            return Err(AppError::validation("Both legs must match when the accounts share a currency")
+                .with_field("to_amount", "Must equal amount"));
+        }
+        Some(to_amount) => to_amount.clone(),
+        None if from_currency == to_currency => payload.amount.clone(),
+        None => {
+            return Err(AppError::validation("to_amount is required between currencies")
+                .with_field("to_amount", "Required between currencies"));
+        }
+    };
+
+    Ok((from_currency, to_currency, to_amount))
+}
+
+async fn create_transfer(
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewTransfer>,
+) -> Result<impl IntoResponse, AppError> {
+    let (from_currency, to_currency, to_amount) = validate_transfer(&pool, &user, &payload).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let transfer = sqlx::query!(
+        r#"
+        INSERT INTO transfers (user_id, from_account_id, to_account_id, created_at)
+        VALUES ($1, $2, $3, COALESCE($4, now()))
+        RETURNING id, created_at
+        "#,
+        user.id,
+        payload.from_account_id,
+        payload.to_account_id,
+        payload.created_at.map(convert_chrono_to_time)
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Money leaves the source account and arrives in the destination account
+    sqlx::query!(
+        r#"
+        INSERT INTO transactions (user_id, transfer_id, kind, account_id, description, amount, currency, created_at)
+        VALUES ($1, $2, 'transfer', $3, $4, $5, $6, $9), ($1, $2, 'transfer', $7, $4, $8, $10, $9)
+        "#,
+        user.id,
+        transfer.id,
+        payload.from_account_id,
+        payload.description,
+        -payload.amount.as_decimal(),
+        from_currency.as_str(),
+        payload.to_account_id,
+        to_amount.as_decimal(),
+        transfer.created_at,
+        to_currency.as_str()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let transfer = fetch_transfer(&pool, &user, transfer.id)
+        .await?
+        .ok_or_else(AppError::internal)?;
+
+    Ok((StatusCode::CREATED, Json(transfer)))
+}
+
+async fn get_transfer(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession
+) -> Result<impl IntoResponse, AppError> {
+    match fetch_transfer(&pool, &user, id).await? {
+        Some(transfer) => Ok(Json(transfer)),
+        None => Err(AppError::not_found("Transfer not found")),
+    }
+}
+
+/// Rewrites a transfer and both of its legs as part of `tx`.
+async fn save_transfer(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    user: &User,
+    id: i32,
+    payload: &NewTransfer,
+) -> Result<(), AppError> {
+    let (from_currency, to_currency, to_amount) = validate_transfer(&mut **tx, user, payload).await?;
+
+    let transfer = sqlx::query!(
+        r#"
+        UPDATE transfers
+        SET from_account_id = $1, to_account_id = $2, created_at = COALESCE($3, created_at)
+        WHERE id = $4 AND user_id = $5
+        RETURNING id, created_at
+        "#,
+        payload.from_account_id,
+        payload.to_account_id,
+        payload.created_at.map(convert_chrono_to_time),
+        id,
+        user.id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(transfer) = transfer else {
+        return Err(AppError::not_found("Transfer not found"));
+    };
+
+    // The source leg is always the negative one
+    sqlx::query!(
+        r#"
+        UPDATE transactions
+        SET
+            account_id = CASE WHEN amount < 0 THEN $2::int ELSE $5::int END,
+            amount = CASE WHEN amount < 0 THEN $3::numeric ELSE $6::numeric END,
+            currency = CASE WHEN amount < 0 THEN $4::text ELSE $7::text END,
+            description = $8,
+            created_at = $9
+        WHERE transfer_id = $1
+        "#,
+        transfer.id,
+        payload.from_account_id,
+        -payload.amount.as_decimal(),
+        from_currency.as_str(),
+        payload.to_account_id,
+        to_amount.as_decimal(),
+        to_currency.as_str(),
+        payload.description,
+        transfer.created_at
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+async fn update_transfer(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    AuthSession(user): AuthSession,
+    Json(payload): Json<NewTransfer>,
+) -> Result<Json<Transfer>, AppError> {
+    let mut tx = pool.begin().await?;
+    save_transfer(&mut tx, &user, id, &payload).await?;
+    tx.commit().await?;
+
+    let transfer = fetch_transfer(&pool, &user, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Transfer not found"))?;
+
+    Ok(Json(transfer))
+}
+
+/// Applies an edit made through `PUT /transactions/{id}` on one leg to the whole transfer.
+/// Description and date always follow; the other leg's amount follows when both share a currency.
+/// Fields a leg can't change through this route are rejected rather than ignored.
+pub async fn update_transfer_from_leg(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    user: &User,
+    transfer_id: i32,
+    leg_id: i32,
+    payload: &NewTransaction,
+) -> Result<(), AppError> {
+    let Some(existing) = fetch_transfer(&mut **tx, user, transfer_id).await? else {
+        return Err(AppError::not_found("Transfer not found"));
+    };
+
+    let (leg_account_id, leg_currency) = if leg_id == existing.from_transaction_id {
+        (existing.from_account_id, &existing.from_currency)
+    } else {
+        (existing.to_account_id, &existing.to_currency)
+    };
+
+    let mut rejected = Vec::new();
+    if payload.category_id.is_some() {
+        rejected.push(("category_id", "Transfer legs have no category"));
+    }
+    if payload.account_id.is_some_and(|account_id| account_id != Some(leg_account_id)) {
+        rejected.push(("account_id", "Change the accounts through /transfers"));
+    }
+    if payload.kind.is_some_and(|kind| kind != TransactionKind::Transfer) {
+        rejected.push(("kind", "Must be transfer"));
+    }
+    if payload.currency.as_ref().is_some_and(|currency| currency != leg_currency) {
+        rejected.push(("currency", "Must match the account's currency"));
+    }
+
+    if !rejected.is_empty() {
+        let error = AppError::validation("Only the description, amount and date of a transfer leg can be edited here");
+        return Err(rejected.into_iter().fold(error, |error, (field, message)| error.with_field(field, message)));
+    }
+
+    let new_amount = payload.amount.abs();
+    let same_currency = existing.from_currency == existing.to_currency;
+
+    let (amount, to_amount) = if leg_id == existing.from_transaction_id {
+        let to_amount = if same_currency { new_amount.clone() } else { existing.to_amount };
+        (new_amount, to_amount)
+    } else {
+        let amount = if same_currency { new_amount.clone() } else { existing.amount };
+        (amount, new_amount)
+    };
+
+    let transfer = NewTransfer {
+        from_account_id: existing.from_account_id,
+        to_account_id: existing.to_account_id,
+        description: payload.description.clone(),
+        amount,
+        to_amount: Some(to_amount),
+        created_at: payload.created_at,
+    };
+
+    save_transfer(tx, user, transfer_id, &transfer).await
+}
+
+async fn delete_transfer(
+    Path(id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Extension(storage): Extension<Storage>,
+    AuthSession(user): AuthSession
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // The legs' attachment files aren't covered by the cascade below
+    let storage_keys = sqlx::query_scalar!(
+        r#"
+        DELETE FROM attachments
+        WHERE user_id = $2
+        AND transaction_id IN (SELECT id FROM transactions WHERE transfer_id = $1 AND user_id = $2)
+        RETURNING storage_key
+        "#,
+        id,
+        user.id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // Both legs go with it through ON DELETE CASCADE
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM transfers
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Transfer not found"));
+    }
+
+    tx.commit().await?;
+
+    remove_stored_files(&storage, &storage_keys).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
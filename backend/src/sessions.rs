@@ -0,0 +1,71 @@
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use sqlx::PgPool;
+use tower_cookies::cookie::time;
+use uuid::Uuid;
+
+use crate::{middleware::ClientInfo, tokens::{generate_token, hash_token}};
+
+pub const SESSION_COOKIE: &str = "session";
+
+/// Sessions expire after this many days without any authenticated request.
+pub const SESSION_TTL_DAYS: i32 = 30;
+
+/// Creates a server-side session for the user and returns the raw token to hand to the client.
+/// Only the token hash is stored, so a leaked database row cannot be replayed as a cookie.
+pub async fn create_session(pool: &PgPool, user_id: Uuid, client: &ClientInfo) -> Result<String, sqlx::Error> {
+    // Opportunistically clean up this user's expired sessions
+    sqlx::query!(
+        r#"DELETE FROM sessions WHERE user_id = $1 AND expires_at <= now()"#,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    let token = generate_token();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO sessions (user_id, token_hash, expires_at, user_agent, ip_address)
+        VALUES ($1, $2, now() + make_interval(days => $3), $4, $5)
+        "#,
+        user_id,
+        hash_token(&token),
+        SESSION_TTL_DAYS,
+        client.user_agent,
+        client.ip_address
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Revokes the session identified by the raw cookie token, if it exists.
+pub async fn revoke_session(pool: &PgPool, token: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"DELETE FROM sessions WHERE token_hash = $1"#,
+        hash_token(token)
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub fn session_cookie(token: String) -> Cookie<'static> {
+    Cookie::build((SESSION_COOKIE, token))
+        .path("/")
+        .http_only(true)
+        .secure(false)
+        .same_site(SameSite::Lax)
+        .build()
+}
+
+pub fn cleared_session_cookie() -> Cookie<'static> {
+    Cookie::build((SESSION_COOKIE, ""))
+        .path("/")
+        .max_age(time::Duration::seconds(0))
+        .http_only(true)
+        .secure(false)
+        .build()
+}
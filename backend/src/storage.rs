@@ -0,0 +1,109 @@
+use std::{env, io, path::PathBuf, sync::Arc};
+
+use axum::body::Bytes;
+use futures::future::BoxFuture;
+
+/// Where attachment files live. Handlers only see this trait, so a store backed by S3 or any
+/// compatible service can replace the local one without touching the routes.
+pub trait AttachmentStore: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, contents: Bytes) -> BoxFuture<'a, io::Result<()>>;
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<Bytes>>;
+
+    /// Removing a key that doesn't exist is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<()>>;
+}
+
+/// The store handlers receive through `Extension`.
+pub type Storage = Arc<dyn AttachmentStore>;
+
+/// Keeps each file under a directory on the local disk, at a path built from its key.
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>) -> LocalStore {
+        LocalStore { root: root.into() }
+    }
+
+    /// Uses `ATTACHMENTS_DIR`, or `./attachments` when it isn't set.
+    pub fn from_env() -> LocalStore {
+        LocalStore::new(env::var("ATTACHMENTS_DIR").unwrap_or_else(|_| "attachments".to_string()))
+    }
+
+    /// Keys are generated by the server, but refuse anything that could escape the root anyway.
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let safe = !key.is_empty()
+            && key.split('/').all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains('\\'));
+
+        if !safe {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid storage key: {}", key)));
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+impl AttachmentStore for LocalStore {
+    fn put<'a>(&'a self, key: &'a str, contents: Bytes) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(path, contents).await
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<Bytes>> {
+        Box::pin(async move {
+            let contents = tokio::fs::read(self.path(key)?).await?;
+            Ok(Bytes::from(contents))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.path(key)?).await {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> LocalStore {
+        LocalStore::new(env::temp_dir().join(format!("attachments-test-{}", uuid::Uuid::new_v4())))
+    }
+
+    #[tokio::test]
+    async fn test_round_trips_files() {
+        let store = temp_store();
+
+        store.put("user/receipt", Bytes::from_static(b"%PDF-1.7")).await.unwrap();
+        assert_eq!(store.get("user/receipt").await.unwrap(), Bytes::from_static(b"%PDF-1.7"));
+
+        store.delete("user/receipt").await.unwrap();
+        assert_eq!(store.get("user/receipt").await.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        // Deleting again is fine
+        store.delete("user/receipt").await.unwrap();
+
+        tokio::fs::remove_dir_all(&store.root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejects_keys_outside_the_root() {
+        let store = temp_store();
+
+        for key in ["", "../secret", "user/../../secret", "/etc/passwd", "user//receipt"] {
+            let err = store.put(key, Bytes::new()).await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{}", key);
+        }
+    }
+}
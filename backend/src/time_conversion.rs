@@ -1,5 +1,5 @@
-use chrono::{DateTime, TimeZone, Utc};
-use sqlx::types::time::OffsetDateTime;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use sqlx::types::time::{Date, OffsetDateTime};
 
 
 
@@ -16,3 +16,38 @@ pub fn convert_chrono_to_time(dt: DateTime<Utc>) -> OffsetDateTime {
 
     OffsetDateTime::from_unix_timestamp_nanos(timestamp as i128 * 1_000_000_000 + nanosecond as i128).expect("Valid timestamp")
 }
+
+pub fn convert_date_to_chrono(date: Date) -> NaiveDate {
+    NaiveDate::from_yo_opt(date.year(), date.ordinal() as u32).expect("Valid date")
+}
+
+pub fn convert_chrono_to_date(date: NaiveDate) -> Date {
+    Date::from_ordinal_date(date.year(), date.ordinal() as u16).expect("Valid date")
+}
+
+/// Parses a `YYYY-MM` month into the first day of that month.
+pub fn parse_month(month: &str) -> Option<NaiveDate> {
+    if month.len() != 7 {
+        return None;
+    }
+
+    NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d").ok()
+}
+
+pub fn format_month(period: NaiveDate) -> String {
+    period.format("%Y-%m").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_months() {
+        assert_eq!(parse_month("2024-03"), NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(parse_month("2024-13"), None);
+        assert_eq!(parse_month("2024-3"), None);
+        assert_eq!(parse_month("2024-03-01"), None);
+        assert_eq!(format_month(parse_month("2024-03").unwrap()), "2024-03");
+    }
+}
@@ -0,0 +1,91 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Generates a random, URL-safe opaque token (256 bits, hex encoded).
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+/// Hashes a token for storage. Tokens are high-entropy, so a fast digest is
+/// enough here and lets us look rows up by hash on every request.
+pub fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+const API_TOKEN_PREFIX: &str = "ecu_";
+
+/// Builds the user-facing API token. The id lets us find the row without scanning,
+/// the secret is what gets verified against the stored hash.
+pub fn format_api_token(id: Uuid, secret: &str) -> String {
+    format!("{}{}_{}", API_TOKEN_PREFIX, id.simple(), secret)
+}
+
+/// Splits an API token back into its row id and secret.
+pub fn parse_api_token(token: &str) -> Option<(Uuid, &str)> {
+    let (id, secret) = token.strip_prefix(API_TOKEN_PREFIX)?.split_once('_')?;
+    if secret.is_empty() {
+        return None;
+    }
+    let id = Uuid::try_parse(id).ok()?;
+    Some((id, secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_tokens_are_unique() {
+        assert_ne!(generate_token(), generate_token());
+        assert_eq!(generate_token().len(), 64);
+    }
+
+    #[test]
+    fn test_hash_is_deterministic() {
+        assert_eq!(hash_token("abc"), hash_token("abc"));
+        assert_ne!(hash_token("abc"), hash_token("abd"));
+    }
+
+    #[test]
+    fn test_api_token_round_trip() {
+        let id = Uuid::new_v4();
+        let secret = generate_token();
+        let token = format_api_token(id, &secret);
+        assert_eq!(parse_api_token(&token), Some((id, secret.as_str())));
+    }
+
+    #[test]
+    fn test_api_token_rejects_malformed() {
+        let id = Uuid::new_v4();
+        assert_eq!(parse_api_token("not-a-token"), None);
+        assert_eq!(parse_api_token(&format!("ecu_{}_", id.simple())), None);
+        assert_eq!(parse_api_token("ecu_garbage_secret"), None);
+        assert_eq!(parse_api_token(&format!("xyz_{}_secret", id.simple())), None);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = b"created_at:42";
+        assert_eq!(from_hex(&to_hex(bytes)), Some(bytes.to_vec()));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
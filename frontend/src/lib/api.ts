@@ -1,16 +1,49 @@
 import { goto } from '$app/navigation';
 import { resolve } from '$app/paths';
 import { auth } from './stores/auth.svelte';
-import type { Transaction, NewTransaction, NewUser, User, Category, NewCategory } from './types';
+import type {
+	Transaction,
+	TransactionPage,
+	NewTransaction,
+	NewUser,
+	User,
+	Category,
+	CategoryTransaction,
+	NewCategory
+} from './types';
 
 const API_BASE = '/api';
 
+/** Pulls the human-readable message out of an error response. */
+async function errorMessage(res: Response): Promise<string> {
+	try {
+		const body = await res.json();
+		return body.message ?? res.statusText;
+	} catch {
+		return res.statusText;
+	}
+}
+
 export async function getTransactions(): Promise<Transaction[]> {
-	const res = await fetch(`${API_BASE}/transactions`, {
-		credentials: 'include'
-	});
-	if (!res.ok) throw new Error('Failed to fetch transactions');
-	return await res.json();
+	const transactions: Transaction[] = [];
+	let cursor: string | null = null;
+
+	// Follow the cursor until every page has been loaded
+	do {
+		const params = new URLSearchParams({ limit: '500' });
+		if (cursor) params.set('cursor', cursor);
+
+		const res = await fetch(`${API_BASE}/transactions?${params}`, {
+			credentials: 'include'
+		});
+		if (!res.ok) throw new Error('Failed to fetch transactions');
+
+		const page: TransactionPage = await res.json();
+		transactions.push(...page.transactions);
+		cursor = page.next_cursor;
+	} while (cursor);
+
+	return transactions;
 }
 
 export async function createTransaction(payload: NewTransaction): Promise<Transaction> {
@@ -59,7 +92,7 @@ export async function signup(payload: NewUser): Promise<void> {
 	if (res.ok) {
 		auth.isLoggedIn = true;
 	} else {
-		throw new Error((await res.text()).valueOf());
+		throw new Error(await errorMessage(res));
 	}
 }
 
@@ -160,7 +193,7 @@ export async function deleteCategory(id: string) {
 	});
 }
 
-export async function getCategoryTransactions(category_id: string): Promise<Transaction[]> {
+export async function getCategoryTransactions(category_id: string): Promise<CategoryTransaction[]> {
 	const res = await fetch(`${API_BASE}/categories/${category_id}/transactions`, {
 		credentials: 'include'
 	});
@@ -183,7 +216,6 @@ export async function uploadUserData(payload: string) {
 	if (res.ok) {
 		alert('Data imported successfully!');
 	} else {
-		const error = await res.text();
-		alert(`Import failed: ${error}`);
+		alert(`Import failed: ${await errorMessage(res)}`);
 	}
 }
@@ -1,16 +1,72 @@
 export type Transaction = {
 	id: number;
-	category: Category;
+	/** null for the legs of a transfer between accounts */
+	category: Category | null;
+	account_id: number | null;
+	transfer: TransferLink | null;
+	kind: 'expense' | 'income' | 'transfer';
 	description: string;
-	amount: number;
+	/** Exact decimal amount, serialized as a string (e.g. "-12.34") */
+	amount: string;
+	currency: string;
+	/** Amount converted to the user's base currency, null when no conversion is available */
+	base_amount: string | null;
+	tags: string[];
+	/** Empty unless the amount is split across several categories */
+	splits: TransactionSplit[];
 	created_at: string;
 };
 
+/** A transaction listed under a category */
+export type CategoryTransaction = Transaction & {
+	/** The part of `amount` filed under the category or its subcategories */
+	category_amount: string;
+};
+
+export type TransactionSplit = {
+	category_id: number;
+	/** Signed like the transaction amount; all splits add up to it */
+	amount: string;
+	memo: string | null;
+};
+
+export type TransactionPage = {
+	transactions: Transaction[];
+	total: number;
+	next_cursor: string | null;
+};
+
 export type NewTransaction = {
 	description: string;
-	category_id: number;
-	amount: number;
+	/** Required for income and expenses; omitted when editing a transfer leg */
+	category_id?: number;
+	/** Left unchanged on update when omitted; null takes the transaction out of its account */
+	account_id?: number | null;
+	amount: string;
+	currency?: string;
 	created_at?: string;
+	/** Left unchanged on update when omitted */
+	tags?: string[];
+	/** Left unchanged on update when omitted; an empty list removes the splits */
+	splits?: TransactionSplit[];
+};
+
+export type Attachment = {
+	id: number;
+	transaction_id: number;
+	file_name: string;
+	/** Detected from the file contents: a PDF, JPEG, PNG, WebP or HEIC */
+	content_type: string;
+	size_bytes: number;
+	created_at: string;
+};
+
+export type TransferLink = {
+	transfer_id: number;
+	from_account_id: number;
+	from_account_name: string;
+	to_account_id: number;
+	to_account_name: string;
 };
 
 export type NewUser = {
@@ -22,6 +78,7 @@ export type User = {
 	id: string;
 	username: string;
 	password_hash: string;
+	base_currency: string;
 	created_at: string;
 };
 